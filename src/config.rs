@@ -1,24 +1,83 @@
-use std::fs::File;
-use std::io::BufWriter;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::os::unix::fs::MetadataExt;
 use std::{io::Read, path::Path};
 
 use super::parser::parse_raw_to_uci;
-use crate::file::TempFile;
+use crate::file::{effective_ids, TempFile, DEFAULT_MODE};
 use crate::imp::{Uci, UciCommand};
-use crate::utils::Result;
+use crate::utils::{Error, Result};
 
 const DEFAULT_LOAD_DIR: &str = "/etc/config";
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SaveOptions {
+    pub mode: u32,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        SaveOptions {
+            mode: DEFAULT_MODE,
+            uid: None,
+            gid: None,
+        }
+    }
+}
+
 pub fn load_config(name: &str, dir: &str) -> Result<Uci> {
     let load_path = if dir.is_empty() {
         Path::new(DEFAULT_LOAD_DIR).join(name)
     } else {
         Path::new(dir).join(name)
     };
-    let mut file = File::open(load_path)?;
+    let file = File::open(&load_path).map_err(|err| _io_error(load_path.display(), err))?;
+
+    load_config_from(name, file)
+}
+
+pub fn load_dir(dir: &str) -> Result<(HashMap<String, Uci>, Vec<Error>)> {
+    let load_dir = if dir.is_empty() {
+        Path::new(DEFAULT_LOAD_DIR)
+    } else {
+        Path::new(dir)
+    };
+
+    let mut configs = HashMap::new();
+    let mut errors = vec![];
+    let dir_error = |err| _io_error(load_dir.display(), err);
+    for entry in fs::read_dir(load_dir).map_err(dir_error)? {
+        let entry = entry.map_err(dir_error)?;
+        if !fs::metadata(entry.path()).map_or(false, |meta| meta.is_file()) {
+            continue;
+        }
+        let name = match entry.file_name().into_string() {
+            Ok(name) if !TempFile::is_tmp_name(&name) => name,
+            _ => continue,
+        };
+        let loaded = File::open(entry.path())
+            .map_err(|err| _io_error(&name, err))
+            .and_then(|file| load_config_from(&name, file));
+        match loaded {
+            Ok(uci) => {
+                configs.insert(name, uci);
+            }
+            Err(err) => errors.push(err),
+        }
+    }
+    Ok((configs, errors))
+}
+
+pub fn load_config_from<R: Read>(name: &str, mut reader: R) -> Result<Uci> {
     let mut string_buffer = String::new();
 
-    file.read_to_string(&mut string_buffer)?;
+    reader
+        .read_to_string(&mut string_buffer)
+        .map_err(|err| _io_error(name, err))?;
 
     let uci = parse_raw_to_uci(name, string_buffer)?;
 
@@ -26,13 +85,57 @@ pub fn load_config(name: &str, dir: &str) -> Result<Uci> {
 }
 
 pub fn save_config(dir: &str, uci: Uci) -> Result<()> {
-    let save_dir = if dir.is_empty() {
+    save_config_with(dir, uci, SaveOptions::default())
+}
+
+pub fn save_config_with(dir: &str, uci: Uci, opts: SaveOptions) -> Result<()> {
+    _write_config(dir, &uci, opts)
+}
+
+pub fn commit_config(dir: &str, uci: &mut Uci) -> Result<bool> {
+    if !uci.is_modified() {
+        return Ok(false);
+    }
+    let opts = _existing_options(&_save_dir(dir).join(uci.package_or_name()));
+    _write_config(dir, uci, opts)?;
+    uci.clear_modified();
+    Ok(true)
+}
+
+/// Keeps the mode and owner of the file being replaced, so committing a
+/// config does not loosen its permissions.
+fn _io_error<T: Display>(name: T, err: io::Error) -> Error {
+    Error::io(format!("config: {}: {}", name, err))
+}
+
+fn _existing_options(path: &Path) -> SaveOptions {
+    let meta = match fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return SaveOptions::default(),
+    };
+    let (uid, gid) = effective_ids();
+    SaveOptions {
+        mode: meta.mode() & 0o7777,
+        uid: Some(meta.uid()).filter(|owner| *owner != uid),
+        gid: Some(meta.gid()).filter(|group| *group != gid),
+    }
+}
+
+fn _save_dir(dir: &str) -> &Path {
+    if dir.is_empty() {
         Path::new(DEFAULT_LOAD_DIR)
     } else {
         Path::new(dir)
-    };
+    }
+}
 
-    let temp_file = TempFile::new(save_dir, uci.get_package())?;
+fn _write_config(dir: &str, uci: &Uci, opts: SaveOptions) -> Result<()> {
+    let save_dir = _save_dir(dir);
+
+    let temp_file = TempFile::with_mode(save_dir, uci.package_or_name(), opts.mode)?;
+    if opts.uid.is_some() || opts.gid.is_some() {
+        temp_file.chown(opts.uid, opts.gid)?;
+    }
 
     let mut buf = BufWriter::new(temp_file);
 
@@ -40,7 +143,7 @@ pub fn save_config(dir: &str, uci: Uci) -> Result<()> {
         Ok(()) => {
             let mut temp_file = buf.into_inner()?;
             temp_file.as_file_mut().sync_all()?;
-            temp_file.persist(save_dir.join(&uci.get_package()))?;
+            temp_file.persist(save_dir.join(uci.package_or_name()))?;
             Ok(())
         }
         Err(err) => {
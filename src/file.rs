@@ -1,5 +1,5 @@
 use std::env;
-use std::ffi::OsString;
+use std::ffi::{CString, OsString};
 use std::fmt;
 use std::fs::{self, create_dir_all, File, OpenOptions};
 use std::io::{self, Read, Seek, SeekFrom, Write};
@@ -7,22 +7,34 @@ use std::iter::repeat_with;
 use std::mem;
 use std::path::{Path, PathBuf};
 
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::OpenOptionsExt;
 
-use crate::utils::{PathError, PersistError};
+use crate::utils::{Error, PathError, PersistError};
 
 use super::Result;
 
 const NUM_RETRIES: u32 = 5;
 const NUM_RAND_CHARS: usize = 6;
+pub(crate) const DEFAULT_MODE: u32 = 0o644;
 
-fn create_named(mut path: PathBuf, open_options: &mut OpenOptions) -> io::Result<TempFile> {
+pub(crate) fn effective_ids() -> (u32, u32) {
+    // SAFETY: geteuid(2) and getegid(2) take no arguments, cannot fail and
+    // only read the calling process's credentials.
+    unsafe { (libc::geteuid(), libc::getegid()) }
+}
+
+fn create_named(
+    mut path: PathBuf,
+    open_options: &mut OpenOptions,
+    mode: u32,
+) -> io::Result<TempFile> {
     if !path.is_absolute() {
         path = env::current_dir()?.join(path)
     }
 
     open_options.read(true).write(true).create_new(true);
-    open_options.mode(0o644);
+    open_options.mode(mode);
     open_options.open(&path).map(|file| TempFile {
         path: path.into_boxed_path(),
         file,
@@ -31,7 +43,6 @@ fn create_named(mut path: PathBuf, open_options: &mut OpenOptions) -> io::Result
 
 fn persist(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<()> {
     if overwrite {
-        println!("{:?} {:?}", old_path, new_path);
         fs::rename(old_path, new_path)?;
     } else {
         fs::hard_link(old_path, new_path)?;
@@ -51,6 +62,10 @@ impl TempFile<File> {
     const SUFFIX: &'static str = ".tmp";
     const APPEND: bool = false;
     pub fn new<P: AsRef<Path>>(dir: P, prefix: String) -> io::Result<TempFile> {
+        Self::with_mode(dir, prefix, DEFAULT_MODE)
+    }
+
+    pub fn with_mode<P: AsRef<Path>>(dir: P, prefix: String, mode: u32) -> io::Result<TempFile> {
         let num_retries = if Self::RANDOM_LEN != 0 {
             NUM_RETRIES
         } else {
@@ -59,7 +74,7 @@ impl TempFile<File> {
 
         for _ in 0..num_retries {
             let path = dir.as_ref().join(Self::tmp_name(&prefix));
-            return match create_named(path, OpenOptions::new().append(Self::APPEND)) {
+            return match create_named(path, OpenOptions::new().append(Self::APPEND), mode) {
                 Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
                 Err(ref e) if e.kind() == io::ErrorKind::AddrInUse => continue,
                 Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
@@ -87,17 +102,33 @@ impl TempFile<File> {
         buf.push(Self::SUFFIX);
         buf
     }
+
+    pub(crate) fn is_tmp_name(name: &str) -> bool {
+        let stem = match name.strip_suffix(Self::SUFFIX) {
+            Some(stem) => stem,
+            None => return false,
+        };
+        match stem.len().checked_sub(Self::RANDOM_LEN + 1) {
+            Some(split) if stem.is_char_boundary(split) => {
+                let (prefix, rand) = stem.split_at(split);
+                !prefix.is_empty()
+                    && rand.starts_with('_')
+                    && rand[1..].chars().all(|c| c.is_ascii_alphanumeric())
+            }
+            _ => false,
+        }
+    }
 }
 
 impl<F> TempFile<F> {
     pub fn close(mut self) -> Result<()> {
-        let result = fs::remove_file(&self.path).map_err(|err| PathError {
+        fs::remove_file(&self.path).map_err(|err| PathError {
             path: self.path.clone().into(),
             error: err,
         })?;
         self.path = PathBuf::new().into_boxed_path();
         mem::forget(self);
-        Ok(result)
+        Ok(())
     }
 
     pub fn persist<P: AsRef<Path>>(mut self, new_path: P) -> Result<()> {
@@ -119,6 +150,26 @@ impl<F> TempFile<F> {
         }
     }
 
+    pub fn chown(&self, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        let describe = |err: io::Error| {
+            Error::io(format!(
+                "failed to change owner of {:?} to uid {:?} gid {:?}: {}",
+                self.path, uid, gid, err
+            ))
+        };
+        let bytes = self.path.as_os_str().as_bytes();
+        let path = CString::new(bytes).map_err(|err| describe(err.into()))?;
+        // chown(2) leaves an id untouched when it is passed as -1.
+        let (owner, group) = (uid.unwrap_or(u32::MAX), gid.unwrap_or(u32::MAX));
+        // SAFETY: `path` is a NUL-terminated CString that stays alive until
+        // the end of this function, so the pointer is valid for the whole call;
+        // chown(2) only reads it.
+        if unsafe { libc::chown(path.as_ptr(), owner, group) } != 0 {
+            return Err(describe(io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
     pub fn as_file(&self) -> &F {
         &self.file
     }
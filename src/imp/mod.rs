@@ -1,11 +1,81 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
 use std::io::{BufWriter, Write};
+use std::net::IpAddr;
+use std::str::FromStr;
 
-use crate::utils::{Error, Result};
+use crate::parser::{parse_raw_to_uci, uci_parse, uci_parse_section};
+use crate::utils::{Error, ErrorKind, Result};
 
 use super::tree::*;
 
+const MEMORY_CONFIG_NAME: &str = "<memory>";
+
 pub struct Uci {
     config: UciConfig,
+    name_cache: HashMap<String, usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReferenceRule {
+    pub sec_type: String,
+    pub option: String,
+    pub target_type: String,
+}
+
+impl ReferenceRule {
+    pub fn new(sec_type: &str, option: &str, target_type: &str) -> Self {
+        Self {
+            sec_type: sec_type.to_string(),
+            option: option.to_string(),
+            target_type: target_type.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    AddSection {
+        typ: String,
+        name: String,
+    },
+    DelSection {
+        section: String,
+    },
+    SetOption {
+        section: String,
+        option: String,
+        values: Vec<String>,
+    },
+    DelOption {
+        section: String,
+        option: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigChange {
+    SectionAdded {
+        section: String,
+        typ: String,
+    },
+    SectionRemoved {
+        section: String,
+        typ: String,
+    },
+    OptionChanged {
+        section: String,
+        option: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ListChanged {
+        section: String,
+        option: String,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
 }
 
 impl Uci {
@@ -15,13 +85,644 @@ impl Uci {
     }
 
     fn default(name: &str) -> Self {
+        let mut config = UciConfig::new(name);
+        config.set_pkg_name(name);
         Self {
-            config: UciConfig::new(name),
+            config,
+            name_cache: HashMap::new(),
         }
     }
 
+    pub fn parse_section_body(typ: &str, name: &str, body: &str) -> Result<UciSection> {
+        uci_parse_section(MEMORY_CONFIG_NAME, typ, name, body)
+    }
+
     pub(super) fn insert_config(&mut self, config: UciConfig) {
         self.config = config;
+        self._rebuild_name_cache();
+    }
+
+    pub fn get_cached(&self, name: &str) -> Option<&UciSection> {
+        if name.starts_with('@') {
+            return self.config.get(name).ok().flatten();
+        }
+        let cached = self.name_cache.get(name).copied();
+        if let Some(sec) = cached.and_then(|idx| self.config.sections.get(idx)) {
+            if sec.name == name {
+                return Some(sec);
+            }
+        }
+        self.config.sections.iter().find(|sec| sec.name == name)
+    }
+
+    fn _rebuild_name_cache(&mut self) {
+        self.name_cache.clear();
+        for (idx, sec) in self.config.sections.iter().enumerate() {
+            if !sec.name.is_empty() {
+                self.name_cache.entry(sec.name.clone()).or_insert(idx);
+            }
+        }
+    }
+
+    fn _cache_last_name(&mut self) {
+        let idx = self.config.sections.len().saturating_sub(1);
+        if let Some(sec) = self.config.sections.last() {
+            if !sec.name.is_empty() {
+                self.name_cache.entry(sec.name.clone()).or_insert(idx);
+            }
+        }
+    }
+
+    pub fn drain_sections_of_type(&mut self, typ: &str) -> Vec<UciSection> {
+        let drained = self.config.drain_all(typ);
+        self._rebuild_name_cache();
+        if !drained.is_empty() {
+            self.config.modified = true;
+        }
+        drained
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.config.modified
+    }
+
+    pub(crate) fn clear_modified(&mut self) {
+        self.config.modified = false;
+    }
+
+    pub fn package_or_name(&self) -> String {
+        if self.config.pkg_name.is_empty() {
+            self.config.name.clone()
+        } else {
+            self.config.pkg_name.clone()
+        }
+    }
+
+    pub fn show(&self) -> Vec<(String, String)> {
+        let package = self.package_or_name();
+        let mut entries = vec![];
+        for sec in self.config.sections.iter() {
+            let prefix = format!("{}.{}", package, self.config.get_section_name(sec));
+            entries.push((prefix.clone(), sec.sec_type.clone()));
+            for opt in sec.options.iter() {
+                match opt.opt_type {
+                    UciOptionType::TypeOption => {
+                        let value = opt.values.first().cloned().unwrap_or_default();
+                        entries.push((format!("{}.{}", prefix, opt.name), value));
+                    }
+                    UciOptionType::TypeList => {
+                        for (i, value) in opt.values.iter().enumerate() {
+                            entries.push((format!("{}.{}.{}", prefix, opt.name, i), value.clone()));
+                        }
+                    }
+                }
+            }
+        }
+        entries
+    }
+
+    pub fn write_sorted<W: Write>(&self, buf: &mut BufWriter<W>) -> Result<()> {
+        self.write_canonical(buf)
+    }
+
+    pub fn write_canonical<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut config = self.config.clone();
+        config.clear_source_edges();
+        for sec in config.sections.iter_mut() {
+            sec.options.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        config.sections.sort_by(|a, b| {
+            (&a.sec_type, &a.name)
+                .cmp(&(&b.sec_type, &b.name))
+                .then_with(|| {
+                    let a_options = a.options.iter().map(|opt| (&opt.name, &opt.values));
+                    let b_options = b.options.iter().map(|opt| (&opt.name, &opt.values));
+                    a_options.cmp(b_options)
+                })
+        });
+        w.write_all(config.to_string().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn write_types<W: Write>(&self, w: &mut W, types: &[&str]) -> Result<()> {
+        w.write_all(self.config.to_string_of_types(types).as_bytes())?;
+        Ok(())
+    }
+
+    pub fn export_filtered(&self, types: &[&str], options: Option<&[&str]>) -> String {
+        let mut config = self.config.clone();
+        let keep_section = |sec: &UciSection| types.contains(&sec.sec_type.as_str());
+        config.sections.retain(keep_section);
+        if let Some(options) = options {
+            let keep_option = |opt: &UciOption| options.contains(&opt.name.as_str());
+            for sec in config.sections.iter_mut() {
+                sec.options.retain(keep_option);
+            }
+        }
+        config.to_string()
+    }
+
+    pub fn get_option_ci(&self, section: &str, option: &str) -> Result<(String, Vec<String>)> {
+        match self.config.get(section)? {
+            Some(sec) => match sec
+                .options
+                .iter()
+                .find(|opt| opt.name.eq_ignore_ascii_case(option))
+            {
+                Some(opt) => Ok((opt.name.clone(), opt.values.clone())),
+                None => Err(Error::not_found(format!(
+                    "option of {}.{} not found",
+                    section, option
+                ))),
+            },
+            None => Err(Error::not_found(format!(
+                "option of {}.{} not found",
+                section, option
+            ))),
+        }
+    }
+
+    pub fn check_duplicate_options(&self) -> Vec<(String, String)> {
+        self.config.duplicate_options()
+    }
+
+    pub fn map_values<F: FnMut(&str, &str, &str) -> Option<String>>(&mut self, f: F) {
+        self.config.map_values(f)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn write_to<W: Write>(&self, w: W) -> Result<()> {
+        let mut buf = BufWriter::new(w);
+        self.write_in(&mut buf)?;
+        buf.flush()?;
+        Ok(())
+    }
+
+    pub fn prepend_section(&mut self, section: UciSection) -> Result<()> {
+        validate_name("section type", &section.sec_type)?;
+        if !section.name.is_empty() {
+            validate_name("section", &section.name)?;
+            if matches!(self.config.get(&section.name), Ok(Some(_))) {
+                return Err(Error::already_exists(format!(
+                    "section '{}' already exists",
+                    section.name
+                )));
+            }
+        }
+        self.config.prepend(section);
+        self._rebuild_name_cache();
+        self.config.modified = true;
+        Ok(())
+    }
+
+    pub fn swap_sections(&mut self, a: usize, b: usize) -> Result<()> {
+        let len = self.config.sections.len();
+        if a >= len || b >= len {
+            return Err(Error::new(format!(
+                "section index out of bounds: {} sections, got {} and {}",
+                len, a, b
+            )));
+        }
+        self.config.sections.swap(a, b);
+        self.config.invalidate_index();
+        self._rebuild_name_cache();
+        self.config.modified = true;
+        Ok(())
+    }
+
+    pub fn header_comments(&self) -> &[String] {
+        &self.config.header_comments
+    }
+
+    pub fn section_comments(&self, section: &str) -> Result<&[String]> {
+        match self.config.get(section)? {
+            Some(sec) => Ok(&sec.comments),
+            None => Err(Error::not_found(format!("section '{}' not found", section))),
+        }
+    }
+
+    pub fn get_section_type(&self, section: &str) -> Result<Option<String>> {
+        match self.config.get(section) {
+            Ok(sec) => Ok(sec.map(|sec| sec.sec_type.clone())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn section_count(&self) -> usize {
+        self.config.len()
+    }
+
+    pub fn sections_iter(&self) -> impl Iterator<Item = &UciSection> {
+        self.config.sections.iter()
+    }
+
+    pub fn sections_by_type(&self, typ: &str) -> Vec<&UciSection> {
+        self.config.sections_by_type(typ)
+    }
+
+    pub fn section_types(&self) -> Vec<String> {
+        let mut types: Vec<String> = vec![];
+        for sec in self.config.sections.iter() {
+            if !types.contains(&sec.sec_type) {
+                types.push(sec.sec_type.clone());
+            }
+        }
+        types
+    }
+
+    pub fn first_index_of_type(&self, typ: &str) -> Option<usize> {
+        self.config
+            .sections
+            .iter()
+            .position(|sec| sec.sec_type == typ)
+    }
+
+    pub fn sections_of_type<'a>(&'a self, typ: &'a str) -> impl Iterator<Item = &'a UciSection> {
+        self.config
+            .sections
+            .iter()
+            .filter(move |sec| sec.sec_type == typ)
+    }
+
+    pub fn sections_sorted_by_option(&self, typ: &str, option: &str) -> Vec<&UciSection> {
+        let mut sections: Vec<&UciSection> = self
+            .config
+            .sections
+            .iter()
+            .filter(|sec| sec.sec_type == typ)
+            .collect();
+        sections.sort_by(|a, b| {
+            let a_value = a.get(option).and_then(|opt| opt.values.first());
+            let b_value = b.get(option).and_then(|opt| opt.values.first());
+            match (a_value, b_value) {
+                (Some(a_value), Some(b_value)) => a_value.cmp(b_value),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        });
+        sections
+    }
+
+    pub fn option_count(&self, section: &str) -> Result<usize> {
+        match self.config.get(section)? {
+            Some(sec) => Ok(sec.options.len()),
+            None => Err(Error::not_found(format!("section '{}' not found", section))),
+        }
+    }
+
+    pub fn try_get_option(&self, section: &str, option: &str) -> Option<&Vec<String>> {
+        match self.config.get(section) {
+            Ok(Some(sec)) => sec.get(option).map(|opt| &opt.values),
+            _ => None,
+        }
+    }
+
+    pub fn list_ref_or<'a>(
+        &'a self,
+        section: &str,
+        option: &str,
+        default: &'a [String],
+    ) -> &'a [String] {
+        match self._lookup_values(section, option) {
+            Ok(values) => values,
+            Err(_) => default,
+        }
+    }
+
+    pub fn render_template(&self, template: &str) -> Result<String> {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("${") {
+            output.push_str(&rest[..start]);
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => {
+                    return Err(Error::new(format!(
+                        "unterminated template reference: {}",
+                        &rest[start..]
+                    )))
+                }
+            };
+            output.push_str(&self._resolve_reference(&rest[start + 2..end])?);
+            rest = &rest[end + 1..];
+        }
+        output.push_str(rest);
+
+        Ok(output)
+    }
+
+    pub fn count_values_matching<F: Fn(&str) -> bool>(
+        &self,
+        section: &str,
+        option: &str,
+        pred: F,
+    ) -> Result<usize> {
+        let values = self._lookup_values(section, option)?;
+        Ok(values.iter().filter(|v| pred(v)).count())
+    }
+
+    pub fn get_option_last_nonempty(&self, section: &str, option: &str) -> Result<Option<String>> {
+        let values = self._lookup_values(section, option)?;
+        Ok(values.iter().rev().find(|v| !v.is_empty()).cloned())
+    }
+
+    pub fn get_scalar(&self, section: &str, option: &str) -> Result<String> {
+        match self._lookup_values(section, option)?.as_slice() {
+            [value] => Ok(value.clone()),
+            values => Err(Error::new(format!(
+                "option of {}.{} expected a single value, found {}",
+                section,
+                option,
+                values.len()
+            ))),
+        }
+    }
+
+    #[cfg(feature = "json")]
+    pub fn option_json(&self, section: &str, option: &str) -> Result<serde_json::Value> {
+        let opt = self._lookup_option(section, option)?;
+        Ok(match opt.opt_type {
+            UciOptionType::TypeOption => {
+                serde_json::Value::String(opt.values.first().cloned().unwrap_or_default())
+            }
+            UciOptionType::TypeList => serde_json::Value::Array(
+                opt.values
+                    .iter()
+                    .map(|v| serde_json::Value::String(v.clone()))
+                    .collect(),
+            ),
+        })
+    }
+
+    pub fn get_option_ip(&self, section: &str, option: &str) -> Result<IpAddr> {
+        match self._lookup_values(section, option)?.first() {
+            Some(value) => parse_ip_value(section, option, value),
+            None => Err(Error::new(format!(
+                "option of {}.{} has no value",
+                section, option
+            ))),
+        }
+    }
+
+    pub fn get_option_ips(&self, section: &str, option: &str) -> Result<Vec<IpAddr>> {
+        self._lookup_values(section, option)?
+            .iter()
+            .map(|value| parse_ip_value(section, option, value))
+            .collect()
+    }
+
+    pub fn validate_references(&self, refs: &[ReferenceRule]) -> Result<()> {
+        for rule in refs {
+            for sec in self.sections_of_type(&rule.sec_type) {
+                let opt = match sec.get(&rule.option) {
+                    Some(opt) => opt,
+                    None => continue,
+                };
+                for value in opt.values.iter() {
+                    let found = self
+                        .sections_of_type(&rule.target_type)
+                        .any(|target| &target.name == value);
+                    if !found {
+                        return Err(Error::new(format!(
+                            "option of {}.{} references missing {} section '{}'",
+                            self.config.get_section_name(sec),
+                            rule.option,
+                            rule.target_type,
+                            value
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_values_printable(&self) -> Result<()> {
+        for sec in self.config.sections.iter() {
+            for opt in sec.options.iter() {
+                let has_control = opt
+                    .values
+                    .iter()
+                    .any(|v| v.chars().any(|c| c.is_ascii_control() && c != '\t'));
+                if has_control {
+                    return Err(Error::new(format!(
+                        "option of {}.{} contains a control character",
+                        self.config.get_section_name(sec),
+                        opt.name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_bool_or_from(
+        &self,
+        section: &str,
+        fallback: &str,
+        option: &str,
+        default: bool,
+    ) -> bool {
+        [section, fallback]
+            .iter()
+            .find_map(|sec| {
+                self.try_get_option(sec, option)
+                    .and_then(|values| values.last())
+                    .and_then(|value| parse_bool_value(value))
+            })
+            .unwrap_or(default)
+    }
+
+    /// Merges `other` into this config. Existing sections and options keep their own
+    /// comments; comments from `other` are carried only by sections and options it adds.
+    pub fn merge_from_with_comments(&mut self, other: &Uci) {
+        self.config.merge_config(other.config.clone());
+        self._rebuild_name_cache();
+        self.config.modified = true;
+    }
+
+    pub fn merge_from_reporting(&mut self, other: &Uci) -> Result<Vec<ConfigChange>> {
+        let before = self._snapshot();
+        self.merge_from_with_comments(other);
+        Ok(before.diff(self))
+    }
+
+    pub fn merge_from_str(&mut self, fragment: &str) -> Result<()> {
+        let other = uci_parse(&self.config.name, fragment.to_string())?;
+        self.config.merge_config(other);
+        self._rebuild_name_cache();
+        self.config.modified = true;
+        Ok(())
+    }
+
+    fn _snapshot(&self) -> Uci {
+        let mut snapshot = Uci::new(&self.config.name);
+        snapshot.insert_config(self.config.clone());
+        snapshot
+    }
+
+    pub fn remove_option(&mut self, section: &str, option: &str) -> Result<Option<UciOption>> {
+        let sec = match self.config.get_mut(section)? {
+            Some(sec) => sec,
+            None => return Err(Error::not_found(format!("section '{}' not found", section))),
+        };
+        match sec.options.iter().position(|opt| opt.name == option) {
+            Some(idx) => {
+                let opt = sec.options.remove(idx);
+                self.config.modified = true;
+                Ok(Some(opt))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn can_apply(&self, changes: &[Change]) -> Result<()> {
+        let mut scratch = self._snapshot();
+        changes
+            .iter()
+            .try_for_each(|change| scratch._apply_change(change))
+    }
+
+    pub fn apply(&mut self, changes: &[Change]) -> Result<()> {
+        self.can_apply(changes)?;
+        changes
+            .iter()
+            .try_for_each(|change| self._apply_change(change))
+    }
+
+    pub fn diff(&self, other: &Uci) -> Vec<ConfigChange> {
+        let mut changes = vec![];
+        for sec in self.config.sections.iter() {
+            let name = self.config.get_section_name(sec);
+            match other.config.get(&name).ok().flatten() {
+                Some(new) if new.sec_type == sec.sec_type => {
+                    diff_section(&name, sec, new, &mut changes)
+                }
+                _ => changes.push(ConfigChange::SectionRemoved {
+                    section: name,
+                    typ: sec.sec_type.clone(),
+                }),
+            }
+        }
+        for sec in other.config.sections.iter() {
+            let name = other.config.get_section_name(sec);
+            match self.config.get(&name).ok().flatten() {
+                Some(old) if old.sec_type == sec.sec_type => {}
+                _ => changes.push(ConfigChange::SectionAdded {
+                    section: name,
+                    typ: sec.sec_type.clone(),
+                }),
+            }
+        }
+        changes
+    }
+
+    pub fn list_entry(&mut self, section: &str, option: &str) -> Result<&mut UciOption> {
+        validate_name("option", option)?;
+        let idx = match self.config.position(section)? {
+            Some(idx) => idx,
+            None => return Err(Error::not_found(format!("section '{}' not found", section))),
+        };
+        self.config.modified = true;
+        let sec = &mut self.config.sections[idx];
+        if sec.get(option).is_none() {
+            sec.add(UciOption::new(option, UciOptionType::TypeList, vec![]));
+        }
+        let opt = sec.get_mut(option).unwrap();
+        opt.set_type(UciOptionType::TypeList);
+        Ok(opt)
+    }
+
+    pub fn set_from_map(
+        &mut self,
+        section: &str,
+        map: &HashMap<String, Vec<String>>,
+    ) -> Result<()> {
+        validate_option_names(map)?;
+        let mut options: Vec<_> = map.iter().collect();
+        options.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (option, values) in options {
+            self.set_option(section, option, values.iter().map(|v| v.as_str()).collect())?;
+        }
+        Ok(())
+    }
+
+    pub fn upsert(&mut self, typ: &str, name: &str, option: &str, values: Vec<&str>) -> Result<()> {
+        if name.is_empty() {
+            return Err(Error::invalid_name(
+                "invalid section name: name must not be empty",
+            ));
+        }
+        match self.config.get(name)? {
+            Some(sec) if sec.sec_type != typ => {
+                return Err(Error::new(format!(
+                    "section '{}' has type '{}', expected '{}'",
+                    name, sec.sec_type, typ
+                )))
+            }
+            Some(_) => {}
+            None => self.add_section(typ, name)?,
+        }
+        self.set_option(name, option, values)
+    }
+
+    pub fn set_section_from_map(
+        &mut self,
+        section: &str,
+        map: &HashMap<String, Vec<String>>,
+        replace: bool,
+    ) -> Result<()> {
+        validate_option_names(map)?;
+        if replace {
+            match self.config.get_mut(section)? {
+                Some(sec) => sec.options.clear(),
+                None => return Err(Error::not_found(format!("section '{}' not found", section))),
+            }
+            self.config.modified = true;
+        }
+        self.set_from_map(section, map)
+    }
+
+    fn _resolve_reference(&self, reference: &str) -> Result<String> {
+        let (section, option) = match reference.rsplit_once('.') {
+            Some((section, option)) if !section.is_empty() && !option.is_empty() => {
+                (section, option)
+            }
+            _ => {
+                return Err(Error::new(format!(
+                    "invalid template reference: ${{{}}}",
+                    reference
+                )))
+            }
+        };
+
+        match option.strip_suffix(']').and_then(|opt| opt.split_once('[')) {
+            Some((option, index)) => {
+                let index = index.parse::<usize>().map_err(|err| {
+                    Error::new(format!(
+                        "invalid template reference: ${{{}}}: {}",
+                        reference, err
+                    ))
+                })?;
+                match self._lookup_values(section, option)?.get(index) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(Error::new(format!(
+                        "invalid template reference: ${{{}}}: index out of bounds",
+                        reference
+                    ))),
+                }
+            }
+            None => Ok(self._lookup_values(section, option)?.join(" ")),
+        }
     }
 
     fn _lookup_values(&self, section: &str, option: &str) -> Result<&Vec<String>> {
@@ -35,12 +736,12 @@ impl Uci {
         match self.config.get(section) {
             Ok(Some(sec)) => match sec.get(option) {
                 Some(opt) => Ok(opt),
-                None => Err(Error::new(format!(
+                None => Err(Error::not_found(format!(
                     "option of {}.{} not found",
                     section, option
                 ))),
             },
-            Ok(None) => Err(Error::new(format!(
+            Ok(None) => Err(Error::not_found(format!(
                 "option of {}.{} not found",
                 section, option
             ))),
@@ -48,6 +749,33 @@ impl Uci {
         }
     }
 
+    fn _apply_change(&mut self, change: &Change) -> Result<()> {
+        match change {
+            Change::AddSection { typ, name } => {
+                if !name.is_empty() && self.config.get(name)?.is_some() {
+                    return Err(Error::already_exists(format!(
+                        "section '{}' already exists",
+                        name
+                    )));
+                }
+                self.add_section(typ, name)
+            }
+            Change::DelSection { section } => match self.config.get(section)? {
+                Some(_) => self.del_section(section),
+                None => Err(Error::not_found(format!("section '{}' not found", section))),
+            },
+            Change::SetOption {
+                section,
+                option,
+                values,
+            } => self.set_option(section, option, values.iter().map(|v| v.as_str()).collect()),
+            Change::DelOption { section, option } => {
+                self._lookup_option(section, option)?;
+                self.del_option(section, option)
+            }
+        }
+    }
+
     fn _set_option_with_type(
         &mut self,
         section: &str,
@@ -55,33 +783,66 @@ impl Uci {
         opt_type: UciOptionType,
         values: Vec<String>,
     ) -> Result<()> {
+        validate_name("option", option)?;
         let sec_opt = self.config.get_mut(section)?;
         match sec_opt {
-            Some(sec) => match sec.get_mut(option) {
-                Some(opt) => {
-                    opt.set_values(values);
-                    Ok(())
-                }
-                None => {
-                    sec.add(UciOption::new(option, opt_type, values));
-                    Ok(())
+            Some(sec) => {
+                match sec.get_mut(option) {
+                    Some(opt) => opt.set_values(values),
+                    None => {
+                        sec.add(UciOption::new(option, opt_type, values));
+                    }
                 }
-            },
-            None => Err(Error::new(format!("section '{}' not found", section))),
+                self.config.modified = true;
+                Ok(())
+            }
+            None => Err(Error::not_found(format!("section '{}' not found", section))),
         }
     }
 }
 
+impl fmt::Display for Uci {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.config.fmt(f)
+    }
+}
+
+impl FromStr for Uci {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_raw_to_uci(MEMORY_CONFIG_NAME, s.to_string())
+    }
+}
+
 pub trait UciCommand {
     fn add_section(&mut self, typ: &str, name: &str) -> Result<()>;
     fn del_option(&mut self, section: &str, option: &str) -> Result<()>;
+    fn rename_option(&mut self, section: &str, old_option: &str, new_option: &str) -> Result<()>;
     fn del_all(&mut self, typ: &str) -> Result<()>;
+    fn clear(&mut self);
     fn del_section(&mut self, section: &str) -> Result<()>;
+    fn rename_section(&mut self, old_name: &str, new_name: &str) -> Result<()>;
+    fn copy_section(&mut self, src: &str, new_name: &str) -> Result<()>;
+    fn move_section(&mut self, section: &str, to_index: usize) -> Result<()>;
     fn get_option(&self, section: &str, option: &str) -> Result<(String, &Vec<String>)>;
     fn get_all_options(&self, section: &str) -> Result<Vec<(String, &Vec<String>)>>;
     fn get_option_last(&self, section: &str, option: &str) -> Result<(String, Option<String>)>;
     fn get_option_first(&self, section: &str, option: &str) -> Result<(String, Option<String>)>;
+    fn get_option_value_at(
+        &self,
+        section: &str,
+        option: &str,
+        index: usize,
+    ) -> Result<Option<String>>;
+    fn get_bool(&self, section: &str, option: &str) -> Result<bool>;
+    fn get_option_parsed<T>(&self, section: &str, option: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display;
     fn get_section(&self, section: &str) -> Result<(String, String)>;
+    fn contains_section(&self, section: &str) -> bool;
+    fn contains_option(&self, section: &str, option: &str) -> bool;
     fn get_all(&self, typ: &str) -> Vec<(String, String)>;
     fn get_all_sections(&self) -> Vec<(String, String)>;
     fn get_section_first(&self, typ: &str) -> Option<(String, String)>;
@@ -89,40 +850,42 @@ pub trait UciCommand {
     fn set_package(&mut self, package: &str) -> Result<()>;
     fn get_package(&self) -> String;
     fn set_option(&mut self, section: &str, option: &str, values: Vec<&str>) -> Result<()>;
+    fn set_option_if_absent(
+        &mut self,
+        section: &str,
+        option: &str,
+        values: Vec<&str>,
+    ) -> Result<bool>;
+    fn set_bool(&mut self, section: &str, option: &str, value: bool) -> Result<()>;
+    fn set_int(&mut self, section: &str, option: &str, value: i64) -> Result<()>;
+    fn add_list_item(&mut self, section: &str, option: &str, value: &str) -> Result<()>;
+    fn del_list_item(&mut self, section: &str, option: &str, value: &str) -> Result<()>;
     fn for_each<F>(&self, typ: &str, func: F)
     where
         F: FnMut(&UciSection);
     fn write_in<W: Write>(&self, buf: &mut BufWriter<W>) -> Result<()>;
+    fn write_in_with<W: Write>(&self, buf: &mut BufWriter<W>, opts: &WriteOptions) -> Result<()>;
 }
 
 impl UciCommand for Uci {
     fn write_in<W: Write>(&self, buf: &mut BufWriter<W>) -> Result<()> {
-        if !self.config.name.is_empty() {
-            buf.write_fmt(format_args!("\npackage '{}'\n", self.config.name))?;
-        }
-
-        for sec in self.config.sections.iter() {
-            if sec.name.is_empty() {
-                buf.write_fmt(format_args!("\nconfig {}\n", sec.sec_type))?;
-            } else {
-                buf.write_fmt(format_args!("\nconfig {} '{}'\n", sec.sec_type, sec.name))?;
-            }
+        self.write_in_with(buf, &WriteOptions::default())
+    }
 
-            for opt in sec.options.iter() {
-                match opt.opt_type {
-                    UciOptionType::TypeOption => {
-                        buf.write_fmt(format_args!("\toption {} '{}'\n", opt.name, opt.values[0]))?;
-                    }
-                    UciOptionType::TypeList => {
-                        for v in opt.values.iter() {
-                            buf.write_fmt(format_args!("\tlist {} '{}'\n", opt.name, v))?;
-                        }
-                    }
-                }
-            }
+    fn write_in_with<W: Write>(&self, buf: &mut BufWriter<W>, opts: &WriteOptions) -> Result<()> {
+        if opts.quote != '\'' && opts.quote != '"' {
+            return Err(Error::new(format!(
+                "unsupported quote character {:?}: expected ' or \"",
+                opts.quote
+            )));
         }
-
-        buf.write_all(b"\n")?;
+        if !opts.indent.chars().all(|c| c == ' ' || c == '\t') {
+            return Err(Error::new(format!(
+                "unsupported indent {:?}: only spaces and tabs are allowed",
+                opts.indent
+            )));
+        }
+        buf.write_all(self.config.to_source_string(opts).as_bytes())?;
         Ok(())
     }
 
@@ -136,6 +899,22 @@ impl UciCommand for Uci {
         Ok((name, values.last().cloned()))
     }
 
+    fn set_option_if_absent(
+        &mut self,
+        section: &str,
+        option: &str,
+        values: Vec<&str>,
+    ) -> Result<bool> {
+        match self.config.get(section)? {
+            Some(sec) if sec.get(option).is_some() => Ok(false),
+            Some(_) => {
+                self.set_option(section, option, values)?;
+                Ok(true)
+            }
+            None => Err(Error::not_found(format!("section '{}' not found", section))),
+        }
+    }
+
     fn set_option(&mut self, section: &str, option: &str, values: Vec<&str>) -> Result<()> {
         if values.len() > 1 {
             self._set_option_with_type(
@@ -154,18 +933,111 @@ impl UciCommand for Uci {
         }
     }
 
+    fn set_bool(&mut self, section: &str, option: &str, value: bool) -> Result<()> {
+        self._set_option_with_type(
+            section,
+            option,
+            UciOptionType::TypeOption,
+            vec![if value { "1" } else { "0" }.to_string()],
+        )
+    }
+
+    fn set_int(&mut self, section: &str, option: &str, value: i64) -> Result<()> {
+        self._set_option_with_type(
+            section,
+            option,
+            UciOptionType::TypeOption,
+            vec![value.to_string()],
+        )
+    }
+
+    fn add_list_item(&mut self, section: &str, option: &str, value: &str) -> Result<()> {
+        validate_name("option", option)?;
+        let sec = match self.config.get_mut(section)? {
+            Some(sec) => sec,
+            None => return Err(Error::not_found(format!("section '{}' not found", section))),
+        };
+        match sec.get_mut(option) {
+            Some(opt) => {
+                opt.set_type(UciOptionType::TypeList);
+                opt.merge_values(vec![value.to_string()]);
+            }
+            None => sec.add(UciOption::new(
+                option,
+                UciOptionType::TypeList,
+                vec![value.to_string()],
+            )),
+        }
+        self.config.modified = true;
+        Ok(())
+    }
+
+    fn del_list_item(&mut self, section: &str, option: &str, value: &str) -> Result<()> {
+        let sec = match self.config.get_mut(section)? {
+            Some(sec) => sec,
+            None => return Err(Error::not_found(format!("section '{}' not found", section))),
+        };
+        match sec.get_mut(option) {
+            Some(opt) => {
+                if let Some(idx) = opt.values.iter().position(|v| v == value) {
+                    opt.remove_value(idx);
+                    if opt.values.is_empty() {
+                        opt.set_type(UciOptionType::TypeList);
+                    }
+                    self.config.modified = true;
+                }
+                Ok(())
+            }
+            None => Err(Error::not_found(format!(
+                "option of {}.{} not found",
+                section, option
+            ))),
+        }
+    }
+
     fn del_option(&mut self, section: &str, option: &str) -> Result<()> {
         let sec_opt = self.config.get_mut(section)?;
         match sec_opt {
             Some(sec) => {
-                self.config.modified = sec.del(option);
+                if sec.del(option) {
+                    self.config.modified = true;
+                }
                 Ok(())
             }
             None => Ok(()),
         }
     }
 
+    fn rename_option(&mut self, section: &str, old_option: &str, new_option: &str) -> Result<()> {
+        validate_name("option", new_option)?;
+        let sec = match self.config.get_mut(section)? {
+            Some(sec) => sec,
+            None => return Err(Error::not_found(format!("section '{}' not found", section))),
+        };
+        if old_option != new_option && sec.get(new_option).is_some() {
+            return Err(Error::already_exists(format!(
+                "option of {}.{} already exists",
+                section, new_option
+            )));
+        }
+        match sec.get_mut(old_option) {
+            Some(opt) => {
+                opt.name = new_option.to_string();
+                self.config.modified = true;
+                Ok(())
+            }
+            None => Err(Error::not_found(format!(
+                "option of {}.{} not found",
+                section, old_option
+            ))),
+        }
+    }
+
     fn add_section(&mut self, typ: &str, name: &str) -> Result<()> {
+        validate_name("section type", typ)?;
+        if !name.is_empty() {
+            validate_name("section", name)?;
+        }
         if name.is_empty() {
             self.config.add(UciSection::new(typ, name));
             self.config.modified = true;
@@ -176,12 +1048,14 @@ impl UciCommand for Uci {
                     if sec.sec_type != typ {
                         self.config.del(name);
                         self.config.add(UciSection::new(typ, name));
+                        self._rebuild_name_cache();
                         self.config.modified = true;
                     }
                     Ok(())
                 }
                 _ => {
                     self.config.add(UciSection::new(typ, name));
+                    self._cache_last_name();
                     self.config.modified = true;
                     Ok(())
                 }
@@ -190,25 +1064,108 @@ impl UciCommand for Uci {
     }
 
     fn del_section(&mut self, section: &str) -> Result<()> {
-        self.config.del(section);
-        self.config.modified = true;
+        if self.config.del(section) {
+            self._rebuild_name_cache();
+            self.config.modified = true;
+        }
         Ok(())
     }
 
+    fn rename_section(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        if new_name.is_empty() {
+            return Err(Error::invalid_name(
+                "invalid section name: name must not be empty",
+            ));
+        }
+        validate_name("section", new_name)?;
+        if old_name != new_name && matches!(self.config.get(new_name), Ok(Some(_))) {
+            return Err(Error::already_exists(format!(
+                "section '{}' already exists",
+                new_name
+            )));
+        }
+        match self.config.get_mut(old_name)? {
+            Some(sec) => {
+                sec.name = new_name.to_string();
+                self._rebuild_name_cache();
+                self.config.modified = true;
+                Ok(())
+            }
+            None => Err(Error::not_found(format!(
+                "section '{}' not found",
+                old_name
+            ))),
+        }
+    }
+
+    fn copy_section(&mut self, src: &str, new_name: &str) -> Result<()> {
+        if new_name.is_empty() {
+            return Err(Error::invalid_name(
+                "invalid section name: name must not be empty",
+            ));
+        }
+        validate_name("section", new_name)?;
+        if matches!(self.config.get(new_name), Ok(Some(_))) {
+            return Err(Error::already_exists(format!(
+                "section '{}' already exists",
+                new_name
+            )));
+        }
+        match self.config.get(src)? {
+            Some(sec) => {
+                let mut copy = sec.clone();
+                copy.name = new_name.to_string();
+                self.config.add(copy);
+                self._cache_last_name();
+                self.config.modified = true;
+                Ok(())
+            }
+            None => Err(Error::not_found(format!("section '{}' not found", src))),
+        }
+    }
+
+    fn move_section(&mut self, section: &str, to_index: usize) -> Result<()> {
+        match self.config.position(section)? {
+            Some(idx) => {
+                let sec = self.config.sections.remove(idx);
+                let to_index = to_index.min(self.config.sections.len());
+                self.config.sections.insert(to_index, sec);
+                self.config.invalidate_index();
+                self._rebuild_name_cache();
+                self.config.modified = true;
+                Ok(())
+            }
+            None => Err(Error::not_found(format!("section '{}' not found", section))),
+        }
+    }
+
     fn set_package(&mut self, package: &str) -> Result<()> {
-        self.config.set_name(package);
+        self.config.set_pkg_name(package);
+        self.config.modified = true;
         Ok(())
     }
 
     fn get_package(&self) -> String {
-        self.config.name.clone()
+        self.package_or_name()
     }
 
     fn del_all(&mut self, typ: &str) -> Result<()> {
+        let count = self.config.sections.len();
         self.config.del_all(typ);
+        if self.config.sections.len() != count {
+            self._rebuild_name_cache();
+            self.config.modified = true;
+        }
         Ok(())
     }
 
+    fn clear(&mut self) {
+        self.config.sections.clear();
+        self.config.invalidate_index();
+        self.name_cache.clear();
+        self.config.modified = true;
+    }
+
     fn get_all_options(&self, section: &str) -> Result<Vec<(String, &Vec<String>)>> {
         let sec_opt = self.config.get(section)?;
         match sec_opt {
@@ -227,6 +1184,44 @@ impl UciCommand for Uci {
         Ok((opt.name.clone(), opt.values.first().cloned()))
     }
 
+    fn get_option_value_at(
+        &self,
+        section: &str,
+        option: &str,
+        index: usize,
+    ) -> Result<Option<String>> {
+        let values = self._lookup_values(section, option)?;
+        Ok(values.get(index).cloned())
+    }
+
+    fn get_bool(&self, section: &str, option: &str) -> Result<bool> {
+        let (name, value) = self.get_option_last(section, option)?;
+        match value.as_deref().and_then(parse_bool_value) {
+            Some(b) => Ok(b),
+            None => Err(Error::new(format!(
+                "option of {}.{} is not a boolean value: {:?}",
+                section,
+                name,
+                value.unwrap_or_default()
+            ))),
+        }
+    }
+
+    fn get_option_parsed<T>(&self, section: &str, option: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let (name, value) = self.get_option_last(section, option)?;
+        let value = value.unwrap_or_default();
+        value.parse::<T>().map_err(|err| {
+            Error::new(format!(
+                "option of {}.{} could not be parsed: {:?}: {}",
+                section, name, value, err
+            ))
+        })
+    }
+
     fn get_section(&self, section: &str) -> Result<(String, String)> {
         let sec_opt = self.config.get(section)?;
         if let Some(sec) = sec_opt {
@@ -235,7 +1230,18 @@ impl UciCommand for Uci {
                 self.config.get_section_name(sec),
             ))
         } else {
-            Err(Error::new("not found target section"))
+            Err(Error::not_found("not found target section"))
+        }
+    }
+
+    fn contains_section(&self, section: &str) -> bool {
+        self.config.find(section).is_some()
+    }
+
+    fn contains_option(&self, section: &str, option: &str) -> bool {
+        match self.config.find(section) {
+            Some(sec) => sec.get(option).is_some(),
+            None => false,
         }
     }
 
@@ -292,18 +1298,84 @@ impl UciCommand for Uci {
     }
 }
 
-
 pub fn is_bool_value(value: &str) -> bool {
+    parse_bool_value(value).unwrap_or(false)
+}
+
+fn parse_ip_value(section: &str, option: &str, value: &str) -> Result<IpAddr> {
+    value.parse::<IpAddr>().map_err(|err| {
+        Error::new(format!(
+            "option of {}.{} is not a valid ip address: {:?}: {}",
+            section, option, value, err
+        ))
+    })
+}
+
+fn diff_section(name: &str, old: &UciSection, new: &UciSection, changes: &mut Vec<ConfigChange>) {
+    let is_new = |opt: &&UciOption| old.get(&opt.name).is_none();
+    for opt in old.options.iter().chain(new.options.iter().filter(is_new)) {
+        let (from, to) = (old.get(&opt.name), new.get(&opt.name));
+        if let (Some(a), Some(b)) = (from, to) {
+            if a.opt_type == b.opt_type && a.values == b.values {
+                continue;
+            }
+        }
+        let is_list = [from, to]
+            .iter()
+            .flatten()
+            .any(|opt| opt.opt_type == UciOptionType::TypeList);
+        let values = |opt: Option<&UciOption>| opt.map(|opt| opt.values.clone());
+        let change = if is_list {
+            ConfigChange::ListChanged {
+                section: name.to_string(),
+                option: opt.name.clone(),
+                from: values(from).unwrap_or_default(),
+                to: values(to).unwrap_or_default(),
+            }
+        } else {
+            ConfigChange::OptionChanged {
+                section: name.to_string(),
+                option: opt.name.clone(),
+                from: from.and_then(|opt| opt.values.first().cloned()),
+                to: to.and_then(|opt| opt.values.first().cloned()),
+            }
+        };
+        changes.push(change);
+    }
+}
+
+pub(crate) fn validate_name(kind: &str, name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c == '-' || c == '_' || c.is_ascii_alphanumeric());
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::invalid_name(format!(
+            "invalid {} name '{}': only [A-Za-z0-9_-] is allowed",
+            kind, name
+        )))
+    }
+}
+
+fn validate_option_names(map: &HashMap<String, Vec<String>>) -> Result<()> {
+    map.keys()
+        .try_for_each(|option| validate_name("option", option))
+}
+
+fn parse_bool_value(value: &str) -> Option<bool> {
     match value {
-        "1" => true,
-        "on" => true,
-        "true" => true,
-        "yes" => true,
-        "enabled" => true,
-        "0" => false,
-        "false" => false,
-        "no" => false,
-        "disabled" => false,
-        _ => false,
+        "1" => Some(true),
+        "on" => Some(true),
+        "true" => Some(true),
+        "yes" => Some(true),
+        "enabled" => Some(true),
+        "0" => Some(false),
+        "off" => Some(false),
+        "false" => Some(false),
+        "no" => Some(false),
+        "disabled" => Some(false),
+        _ => None,
     }
 }
@@ -5,7 +5,14 @@ mod parser;
 mod tree;
 mod utils;
 
-pub use config::{load_config, save_config};
-pub use parser::parse_raw_to_uci;
-pub use utils::{Error, Result};
-pub use imp::{is_bool_value, Uci, UciCommand};
+pub use config::{
+    commit_config, load_config, load_config_from, load_dir, save_config, save_config_with,
+    SaveOptions,
+};
+pub use parser::{
+    parse_raw_to_uci, parse_raw_to_uci_with, parse_streaming, tokenize, uci_parse_collect,
+    uci_parse_strict, uci_parse_with, ParseOptions, ScanTokenType, Token, TokenItem, TokenItemType,
+};
+pub use utils::{compact_output, Error, ErrorKind, Result};
+pub use imp::{is_bool_value, Change, ConfigChange, ReferenceRule, Uci, UciCommand};
+pub use tree::{UciConfig, UciOption, UciOptionType, UciQuoteStyle, UciSection, WriteOptions};
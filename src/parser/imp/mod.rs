@@ -4,11 +4,11 @@ use crate::{utils::{Error, Result}};
 
 use super::{
     lexer::Lexer,
-    token::{ScanTokenType, Token, TokenItem, TokenItemType},
+    token::{KeyWord, ScanTokenType, Token, TokenItem, TokenItemType},
 };
 
-use super::super::tree::{UciConfig, UciOption, UciOptionType, UciSection};
-use super::super::imp::Uci;
+use super::super::tree::{UciConfig, UciOption, UciOptionType, UciQuoteStyle, UciSection};
+use super::super::imp::{validate_name, Uci};
 
 pub struct Scanner {
     lexer: Lexer,
@@ -16,6 +16,13 @@ pub struct Scanner {
     last: Option<TokenItem>,
     curr: Vec<TokenItem>,
     tokens: Option<VecDeque<Token>>,
+    allow_empty_option: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub allow_empty_option: bool,
+    pub skip_leading_until_keyword: bool,
 }
 
 trait ScannerStateMachine {
@@ -50,9 +57,28 @@ impl Scanner {
             curr: vec![],
             tokens: Some(VecDeque::new()),
             last: None,
+            allow_empty_option: false,
         }
     }
 
+    fn starting_at_line(mut self, line: usize) -> Self {
+        self.lexer = self.lexer.starting_at_line(line);
+        self
+    }
+
+    fn with_options(mut self, opts: ParseOptions) -> Self {
+        self.allow_empty_option = opts.allow_empty_option;
+        self
+    }
+
+    fn input(&self) -> &str {
+        self.lexer.input()
+    }
+
+    fn token_start(&self) -> usize {
+        self.lexer.token_start()
+    }
+
     fn eof(&self) -> Option<Token> {
         None
     }
@@ -106,13 +132,15 @@ impl Scanner {
         self.curr = vec![];
     }
 
-    fn emit_error(&mut self, error: &str) -> Option<ScannerState> {
+    fn emit_error(&mut self, error: &str, it: &TokenItem) -> Option<ScannerState> {
         self.tokens.as_mut().unwrap().push_back(Token {
             typ: ScanTokenType::Error,
             items: vec![TokenItem {
                 typ: TokenItemType::Error,
                 val: error.to_owned(),
-                pos: 0,
+                pos: it.pos,
+                line: it.line,
+                col: it.col,
             }],
         });
         None
@@ -153,9 +181,14 @@ impl ScannerStateMachine for Scanner {
         match self.next_item() {
             it if it.typ == TokenItemType::Package => Some(ScannerState::Package),
             it if it.typ == TokenItemType::Config => Some(ScannerState::Section),
-            it if it.typ == TokenItemType::Error => self.emit_error(&it.val),
+            it if it.typ == TokenItemType::Comment => {
+                self.curr.push(it);
+                self.emit(ScanTokenType::Comment);
+                Some(ScannerState::Start)
+            }
+            it if it.typ == TokenItemType::Error => self.emit_error(&it.val, &it),
             it if it.typ == TokenItemType::Eof => None,
-            _ => self.emit_error("expected package or config token"),
+            it => self.emit_error("expected package or config token", &it),
         }
     }
 
@@ -166,8 +199,8 @@ impl ScannerStateMachine for Scanner {
                 self.emit(ScanTokenType::Package);
                 Some(ScannerState::Start)
             }
-            it if it.typ == TokenItemType::Error => self.emit_error(&it.val),
-            _ => self.emit_error("expected string value while parsing package"),
+            it if it.typ == TokenItemType::Error => self.emit_error(&it.val, &it),
+            it => self.emit_error("expected string value while parsing package", &it),
         }
     }
 
@@ -182,8 +215,8 @@ impl ScannerStateMachine for Scanner {
                 self.emit(ScanTokenType::Section);
                 Some(ScannerState::Option)
             }
-            it if it.typ == TokenItemType::Error => self.emit_error(&it.val),
-            _ => self.emit_error("expected identifier while parsing config section"),
+            it if it.typ == TokenItemType::Error => self.emit_error(&it.val, &it),
+            it => self.emit_error("expected identifier while parsing config section", &it),
         }
     }
 
@@ -191,7 +224,12 @@ impl ScannerStateMachine for Scanner {
         match self.next_item() {
             it if it.typ == TokenItemType::Option => Some(ScannerState::OptionName),
             it if it.typ == TokenItemType::List => Some(ScannerState::ListName),
-            it if it.typ == TokenItemType::Error => self.emit_error(&it.val),
+            it if it.typ == TokenItemType::Comment => {
+                self.curr.push(it);
+                self.emit(ScanTokenType::Comment);
+                Some(ScannerState::Option)
+            }
+            it if it.typ == TokenItemType::Error => self.emit_error(&it.val, &it),
             it => {
                 self.backup(&it);
                 Some(ScannerState::Start)
@@ -203,7 +241,8 @@ impl ScannerStateMachine for Scanner {
         if self.accept_once(TokenItemType::Ident) {
             Some(ScannerState::OptionValue)
         } else {
-            self.emit_error("expected option name")
+            let it = self.peek();
+            self.emit_error("expected option name", &it)
         }
     }
 
@@ -211,7 +250,8 @@ impl ScannerStateMachine for Scanner {
         if self.accept_once(TokenItemType::Ident) {
             Some(ScannerState::ListValue)
         } else {
-            self.emit_error("expected option name")
+            let it = self.peek();
+            self.emit_error("expected option name", &it)
         }
     }
 
@@ -222,8 +262,22 @@ impl ScannerStateMachine for Scanner {
                 self.emit(ScanTokenType::Option);
                 Some(ScannerState::Option)
             }
-            it if it.typ == TokenItemType::Error => self.emit_error(&it.val),
-            _ => self.emit_error("expected option value"),
+            it if it.typ == TokenItemType::Error => self.emit_error(&it.val, &it),
+            it if self.allow_empty_option => {
+                self.backup(&it);
+                let name = &self.curr[0];
+                let empty = TokenItem {
+                    typ: TokenItemType::String,
+                    val: String::new(),
+                    pos: name.pos + name.val.len(),
+                    line: name.line,
+                    col: name.col + name.val.chars().count(),
+                };
+                self.curr.push(empty);
+                self.emit(ScanTokenType::Option);
+                Some(ScannerState::Option)
+            }
+            it => self.emit_error("expected option value", &it),
         }
     }
 
@@ -234,33 +288,81 @@ impl ScannerStateMachine for Scanner {
                 self.emit(ScanTokenType::List);
                 Some(ScannerState::Option)
             }
-            it if it.typ == TokenItemType::Error => self.emit_error(&it.val),
-            _ => self.emit_error("expected option value"),
+            it if it.typ == TokenItemType::Error => self.emit_error(&it.val, &it),
+            it => self.emit_error("expected option value", &it),
         }
     }
 }
 
-pub fn uci_parse(name: &str, input: String) -> Result<UciConfig> {
-    let mut scanner = Scanner::new(name, input);
-    let mut cfg = UciConfig::new(name);
+enum ParseEvent {
+    Package(String, Vec<String>),
+    Section(UciSection),
+}
+
+fn parse_events<F>(
+    name: &str,
+    input: String,
+    line: usize,
+    opts: ParseOptions,
+    mut f: F,
+) -> Result<Vec<String>>
+where
+    F: FnMut(ParseEvent) -> Result<()>,
+{
+    let quote_of = |it: &TokenItem, input: &str| match input.as_bytes().get(it.pos) {
+        Some(b'"') => UciQuoteStyle::Double,
+        _ => UciQuoteStyle::Single,
+    };
+    let source = input.clone();
+    let mut scanner = Scanner::new(name, input)
+        .starting_at_line(line)
+        .with_options(opts);
     let mut sec: Option<UciSection> = None;
+    let mut package = String::new();
+    let mut comments: Vec<String> = vec![];
+    let mut last_value: Option<(String, usize)> = None;
     match scanner.try_for_each(|tok: Token| -> Result<()> {
         match tok.typ {
             ScanTokenType::Error => {
-                return Err(Error::new(format!("parse error: {}", tok.items[0].val)));
+                let it = &tok.items[0];
+                let mut err = Error::parse(format!("parse error: {}", it.val));
+                err.pos = Some(it.pos);
+                err.line = Some(it.line);
+                err.col = Some(it.col);
+                return Err(err);
             }
             ScanTokenType::Package => {
-                cfg.set_name(&tok.items[0].val);
+                if let Some(s) = sec.take() {
+                    f(ParseEvent::Section(s))?;
+                };
+                let leading_comments = if package.is_empty() {
+                    std::mem::take(&mut comments)
+                } else {
+                    vec![]
+                };
+                package = tok.items[0].val.clone();
+                last_value = None;
+                f(ParseEvent::Package(package.clone(), leading_comments))?;
             }
-            ScanTokenType::Section => {
-                if sec.is_some() {
-                    if let Some(s) = sec.as_ref() {
-                        if s.sec_type != String::new() && s.name != String::new() {
-                            cfg.merge(s.clone());
-                        } else {
-                            cfg.add(s.clone());
+            ScanTokenType::Comment => {
+                let it = &tok.items[0];
+                let line_start = source[..it.pos].rfind('\n').map_or(0, |idx| idx + 1);
+                let inline = !source[line_start..it.pos].trim().is_empty();
+                match (sec.as_mut(), &last_value) {
+                    (Some(s), Some((name, idx))) if inline => {
+                        if let Some(opt) = s.get_mut(name) {
+                            opt.set_inline_comment(*idx, &it.val);
                         }
-                    };
+                    }
+                    (Some(s), None) if inline && s.options.is_empty() => {
+                        s.inline_comment = Some(it.val.clone());
+                    }
+                    _ => comments.push(it.val.clone()),
+                }
+            }
+            ScanTokenType::Section => {
+                if let Some(s) = sec.take() {
+                    f(ParseEvent::Section(s))?;
                 };
                 if tok.items.len() == 2 {
                     let sec_typ = &tok.items[0].val;
@@ -270,51 +372,313 @@ pub fn uci_parse(name: &str, input: String) -> Result<UciConfig> {
                     let sec_typ = &tok.items[0].val;
                     sec = Some(UciSection::new(sec_typ, ""));
                 }
+                if let Some(s) = sec.as_mut() {
+                    s.comments = std::mem::take(&mut comments);
+                    s.package = package.clone();
+                };
+                last_value = None;
             }
             ScanTokenType::Option => {
                 let name = &tok.items[0].val;
                 let val = tok.items[1].val.clone();
+                let quote = quote_of(&tok.items[1], &source);
 
                 if let Some(opt) = sec.as_mut().unwrap().get_mut(name) {
                     opt.set_values(vec![val]);
+                    opt.set_quote(0, quote);
+                    opt.comments.append(&mut comments);
                 } else if let Some(s) = sec.as_mut() {
-                    s.add(UciOption::new(name, UciOptionType::TypeOption, vec![val]))
+                    let mut opt = UciOption::new(name, UciOptionType::TypeOption, vec![val]);
+                    opt.set_quote(0, quote);
+                    opt.comments = std::mem::take(&mut comments);
+                    s.add(opt)
                 };
+                last_value = Some((name.clone(), 0));
             }
             ScanTokenType::List => {
                 let name = &tok.items[0].val;
                 let val = tok.items[1].val.clone();
+                let quote = quote_of(&tok.items[1], &source);
 
                 if let Some(opt) = sec.as_mut().unwrap().get_mut(name) {
-                    opt.merge_values(vec![val]);
+                    let len = opt.values.len();
+                    opt.merge_values(vec![val.clone()]);
+                    if opt.values.len() > len {
+                        opt.set_quote(len, quote);
+                    }
+                    opt.comments.append(&mut comments);
+                    last_value = opt
+                        .values
+                        .iter()
+                        .position(|v| *v == val)
+                        .map(|idx| (name.clone(), idx));
                 } else if let Some(s) = sec.as_mut() {
-                    s.add(UciOption::new(name, UciOptionType::TypeList, vec![val]))
+                    let mut opt = UciOption::new(name, UciOptionType::TypeList, vec![val]);
+                    opt.set_quote(0, quote);
+                    opt.comments = std::mem::take(&mut comments);
+                    s.add(opt);
+                    last_value = Some((name.clone(), 0));
                 };
             }
         };
         Ok(())
     }) {
         Ok(_) => {
-            if sec.is_some() {
-                if let Some(s) = sec.as_ref() {
-                    if s.sec_type != String::new() && s.name != String::new() {
-                        cfg.merge(s.clone());
-                    } else {
-                        cfg.add(s.clone());
-                    }
-                };
+            if let Some(s) = sec.take() {
+                f(ParseEvent::Section(s))?;
             };
-         Ok(cfg)
+            Ok(comments)
         }
-        Err(err) => {
+        Err(mut err) => {
+            if let Some(pos) = err.pos {
+                err.span = Some(error_span(scanner.input(), scanner.token_start(), pos));
+            }
             scanner.stop();
             Err(err)
         }
     }
 }
 
+/// Span of the input an error at `pos` points at. Lexer errors raised inside a
+/// token, such as an unterminated quote, cover the token from `token_start` up
+/// to `pos`; other errors cover the offending token starting at `pos`.
+fn error_span(input: &str, token_start: usize, pos: usize) -> (usize, usize) {
+    if token_start < pos {
+        return (token_start, pos);
+    }
+    let rest = input.get(pos..).unwrap_or_default();
+    let len = match rest.chars().next() {
+        Some(q) if q == '\'' || q == '"' => match rest[1..].find(|c| c == q || c == '\n') {
+            Some(idx) if rest[1 + idx..].starts_with(q) => idx + 2,
+            Some(idx) => idx + 1,
+            None => rest.len(),
+        },
+        _ => rest.find(char::is_whitespace).unwrap_or(rest.len()),
+    };
+    (pos, pos + len)
+}
+
+fn apply_event(cfg: &mut UciConfig, event: ParseEvent) {
+    match event {
+        ParseEvent::Package(pkg_name, mut comments) => {
+            if cfg.pkg_name.is_empty() {
+                cfg.set_pkg_name(&pkg_name)
+            }
+            cfg.header_comments.append(&mut comments);
+        }
+        ParseEvent::Section(mut s) => {
+            if s.package == cfg.pkg_name {
+                s.package.clear();
+            }
+            if !s.sec_type.is_empty() && !s.name.is_empty() {
+                cfg.merge(s);
+            } else {
+                cfg.add(s);
+            }
+        }
+    };
+}
+
+pub fn uci_parse(name: &str, input: String) -> Result<UciConfig> {
+    uci_parse_with(name, input, ParseOptions::default())
+}
+
+pub fn uci_parse_with(name: &str, input: String, opts: ParseOptions) -> Result<UciConfig> {
+    let mut cfg = UciConfig::new(name);
+    let input = if opts.skip_leading_until_keyword {
+        let (blanked, kept) = blank_leading_lines(&input);
+        cfg.set_source_edges(&kept);
+        blanked
+    } else {
+        cfg.set_source_edges(&input);
+        input
+    };
+    let trailing_comments = parse_events(name, input, 1, opts, |event| {
+        apply_event(&mut cfg, event);
+        Ok(())
+    })?;
+    cfg.trailing_comments = trailing_comments;
+    Ok(cfg)
+}
+
+/// Blanks out the lines before the first `config`/`package` keyword that are
+/// neither comments nor empty. Byte offsets and line numbers are unchanged;
+/// the second string has those lines removed.
+fn blank_leading_lines(input: &str) -> (String, String) {
+    let mut blanked = String::with_capacity(input.len());
+    let mut kept = String::with_capacity(input.len());
+    let mut leading = true;
+    for line in input.split_inclusive('\n') {
+        let curr = line.trim_start();
+        if curr.starts_with(KeyWord::KW_CONFIG) || curr.starts_with(KeyWord::KW_PACKAGE) {
+            leading = false;
+        }
+        if leading && !curr.is_empty() && !curr.starts_with('#') {
+            for c in line.chars() {
+                match c {
+                    '\r' | '\n' => blanked.push(c),
+                    _ => blanked.extend(std::iter::repeat(' ').take(c.len_utf8())),
+                }
+            }
+        } else {
+            blanked.push_str(line);
+            kept.push_str(line);
+        }
+    }
+    (blanked, kept)
+}
+
+pub fn uci_parse_strict(name: &str, input: String) -> Result<UciConfig> {
+    let mut cfg = UciConfig::new(name);
+    cfg.set_source_edges(&input);
+    let trailing_comments = parse_events(name, input, 1, ParseOptions::default(), |event| {
+        if let ParseEvent::Section(s) = &event {
+            let duplicate = !s.name.is_empty()
+                && cfg
+                    .sections
+                    .iter()
+                    .any(|sec| cfg.get_section_name(sec) == s.name);
+            if duplicate {
+                return Err(Error::parse(format!(
+                    "parse error: duplicate section '{}'",
+                    s.name
+                )));
+            }
+        }
+        apply_event(&mut cfg, event);
+        Ok(())
+    })?;
+    cfg.trailing_comments = trailing_comments;
+    Ok(cfg)
+}
+
+/// Parses `input`, resyncing at the next `config`/`package` line after each
+/// error. The section being parsed when an error occurs is dropped entirely,
+/// including the options read before the error; sections after the resync
+/// stay in the package they were declared in.
+pub fn uci_parse_collect(name: &str, input: String) -> (Option<UciConfig>, Vec<Error>) {
+    let mut cfg = UciConfig::new(name);
+    cfg.set_source_edges(&input);
+    let mut errors = vec![];
+    let mut package = String::new();
+    let (mut line, mut offset) = (1, 0);
+    loop {
+        let rest = input[offset..].to_string();
+        let result = parse_events(name, rest, line, ParseOptions::default(), |mut event| {
+            match event {
+                ParseEvent::Package(ref pkg_name, _) => package = pkg_name.clone(),
+                ParseEvent::Section(ref mut s) if s.package.is_empty() => {
+                    s.package = package.clone()
+                }
+                _ => {}
+            }
+            apply_event(&mut cfg, event);
+            Ok(())
+        });
+        match result {
+            Ok(trailing_comments) => {
+                cfg.trailing_comments = trailing_comments;
+                break;
+            }
+            Err(mut err) => {
+                err.pos = err.pos.map(|pos| pos + offset);
+                err.span = err.span.map(|(start, end)| (start + offset, end + offset));
+                let resync = err.line.and_then(|l| next_keyword_line(&input, l));
+                errors.push(err);
+                match resync {
+                    Some((next_line, next_offset)) => {
+                        line = next_line;
+                        offset = next_offset;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+    if errors.is_empty() || !cfg.sections.is_empty() {
+        (Some(cfg), errors)
+    } else {
+        (None, errors)
+    }
+}
+
+fn next_keyword_line(input: &str, after: usize) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    for (idx, line) in input.split_inclusive('\n').enumerate() {
+        let curr = line.trim_start();
+        if idx + 1 > after
+            && (curr.starts_with(KeyWord::KW_CONFIG) || curr.starts_with(KeyWord::KW_PACKAGE))
+        {
+            return Some((idx + 1, offset));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+pub fn parse_streaming<F>(name: &str, input: &str, mut f: F) -> Result<()>
+where
+    F: FnMut(UciSection) -> Result<()>,
+{
+    parse_events(
+        name,
+        input.to_string(),
+        1,
+        ParseOptions::default(),
+        |event| match event {
+            ParseEvent::Package(..) => Ok(()),
+            ParseEvent::Section(s) => f(s),
+        },
+    )?;
+    Ok(())
+}
+
+/// Parses the option and list lines of a single section. The synthesized
+/// header is numbered line 0, so errors report lines relative to `body`.
+pub(crate) fn uci_parse_section(
+    name: &str,
+    typ: &str,
+    sec_name: &str,
+    body: &str,
+) -> Result<UciSection> {
+    validate_name("section type", typ)?;
+    if !sec_name.is_empty() {
+        validate_name("section", sec_name)?;
+    }
+    let header = if sec_name.is_empty() {
+        format!("config {}\n", typ)
+    } else {
+        format!("config {} '{}'\n", typ, sec_name)
+    };
+    let mut section = None;
+    parse_events(
+        name,
+        header + body + "\n",
+        0,
+        ParseOptions::default(),
+        |event| match event {
+            ParseEvent::Section(s) if section.is_none() => {
+                section = Some(s);
+                Ok(())
+            }
+            _ => Err(Error::parse(
+                "invalid section body: expected only option and list lines",
+            )),
+        },
+    )?;
+    section.ok_or_else(|| Error::parse("invalid section body: missing section"))
+}
+
+pub fn tokenize(name: &str, input: &str) -> impl Iterator<Item = Token> {
+    Scanner::new(name, input.to_string())
+}
+
 pub fn parse_raw_to_uci(name: &str, input: String) -> Result<Uci> {
-    let cfg = uci_parse(name, input)?;
+    parse_raw_to_uci_with(name, input, ParseOptions::default())
+}
+
+pub fn parse_raw_to_uci_with(name: &str, input: String, opts: ParseOptions) -> Result<Uci> {
+    let cfg = uci_parse_with(name, input, opts)?;
     let mut uci = Uci::new(name);
     uci.insert_config(cfg);
     Ok(uci)
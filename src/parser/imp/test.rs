@@ -24,11 +24,15 @@
                                 typ: TokenItemType::Ident,
                                 val: "sectiontype".to_string(),
                                 pos: 0,
+                                line: 0,
+                                col: 0,
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "sectionname".to_string(),
                                 pos: 0,
+                                line: 0,
+                                col: 0,
                             },
                         ],
                     },
@@ -39,11 +43,15 @@
                                 typ: TokenItemType::Ident,
                                 val: "optionname".to_string(),
                                 pos: 0,
+                                line: 0,
+                                col: 0,
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: String::new(),
                                 pos: 0,
+                                line: 0,
+                                col: 0,
                             },
                         ],
                     },
@@ -60,11 +68,15 @@
                                 typ: TokenItemType::Ident,
                                 val: "sectiontype".to_string(),
                                 pos: 0,
+                                line: 0,
+                                col: 0,
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "sectionname".to_string(),
                                 pos: 0,
+                                line: 0,
+                                col: 0,
                             },
                         ],
                     },
@@ -75,11 +87,15 @@
                                 typ: TokenItemType::Ident,
                                 val: "optionname".to_string(),
                                 pos: 0,
+                                line: 0,
+                                col: 0,
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "optionvalue".to_string(),
                                 pos: 0,
+                                line: 0,
+                                col: 0,
                             },
                         ],
                     },
@@ -94,7 +110,7 @@
                         items: vec![
                             TokenItem {
                                 typ: TokenItemType::String,
-                                val: "pkgname".to_string(), pos: 0
+                                val: "pkgname".to_string(), pos: 0, line: 0, col: 0
                             },
                         ]
                     },
@@ -103,7 +119,7 @@
                         items: vec![
                             TokenItem {
                                 typ: TokenItemType::Ident,
-                                val: "empty".to_string(), pos: 0
+                                val: "empty".to_string(), pos: 0, line: 0, col: 0
                             },
                         ]
                     },
@@ -113,12 +129,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "squoted".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "sqname".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     },
@@ -128,12 +148,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "dquoted".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "dqname".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     },
@@ -143,12 +167,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "multiline".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "line1\\\n\tline2".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -164,12 +192,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "foo".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "bar".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -179,12 +211,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "answer".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "42".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     },
@@ -200,12 +236,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "foo".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "named".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     },
@@ -215,12 +255,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "pos".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem{
                                 typ: TokenItemType::String,
                                 val: "0".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -230,12 +274,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "unnamed".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "0".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -245,12 +293,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "list".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "0".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -260,7 +312,9 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "foo".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     },
@@ -270,12 +324,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "pos".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "1".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -285,12 +343,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "unnamed".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "1".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -299,12 +361,14 @@
                         items: vec![
                             TokenItem {
                                 typ: TokenItemType::Ident,
-                                val: "list".to_string(), pos: 0
+                                val: "list".to_string(), pos: 0, line: 0, col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "10".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -314,7 +378,9 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "foo".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -324,12 +390,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "pos".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "2".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -339,12 +409,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "unnamed".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "1".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -354,12 +428,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "list".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "20".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -369,12 +447,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "foo".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "named".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -384,12 +466,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "pos".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "3".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -399,12 +485,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "unnamed".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "0".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -414,12 +504,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "list".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "30".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -435,12 +529,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "wifi-device".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "wl0".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -450,12 +548,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "type".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "broadcom".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -465,12 +567,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "channel".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "6".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -480,12 +586,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "wifi-iface".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "wifi0".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -495,12 +605,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "device".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "wl0".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -510,12 +624,16 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "mode".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "ap".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     },
@@ -525,13 +643,39 @@
                 "commented",
                 "\n# heading\n\n# another heading\nconfig foo\n\toption opt1 1\n\t# option opt1 2\n\toption opt2 3 # baa\n\toption opt3 hello\n\n# a comment block spanning\n# multiple lines, surrounded\n# by empty lines\n\n# eof\n".to_string(),
                 vec![
+                    Token {
+                        typ: ScanTokenType::Comment,
+                        items: vec![
+                            TokenItem {
+                                typ: TokenItemType::Comment,
+                                val: "# heading".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
+                            },
+                        ]
+                    },
+                    Token {
+                        typ: ScanTokenType::Comment,
+                        items: vec![
+                            TokenItem {
+                                typ: TokenItemType::Comment,
+                                val: "# another heading".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
+                            },
+                        ]
+                    },
                     Token {
                         typ: ScanTokenType::Section,
                         items: vec![
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "foo".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -541,12 +685,28 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "opt1".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "1".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
+                            },
+                        ]
+                    },
+                    Token {
+                        typ: ScanTokenType::Comment,
+                        items: vec![
+                            TokenItem {
+                                typ: TokenItemType::Comment,
+                                val: "# option opt1 2".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -556,12 +716,28 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "opt2".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "3".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
+                            },
+                        ]
+                    },
+                    Token {
+                        typ: ScanTokenType::Comment,
+                        items: vec![
+                            TokenItem {
+                                typ: TokenItemType::Comment,
+                                val: "# baa".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -571,15 +747,67 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "opt3".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                             TokenItem {
                                 typ: TokenItemType::String,
                                 val: "hello".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     },
+                    Token {
+                        typ: ScanTokenType::Comment,
+                        items: vec![
+                            TokenItem {
+                                typ: TokenItemType::Comment,
+                                val: "# a comment block spanning".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
+                            },
+                        ]
+                    },
+                    Token {
+                        typ: ScanTokenType::Comment,
+                        items: vec![
+                            TokenItem {
+                                typ: TokenItemType::Comment,
+                                val: "# multiple lines, surrounded".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
+                            },
+                        ]
+                    },
+                    Token {
+                        typ: ScanTokenType::Comment,
+                        items: vec![
+                            TokenItem {
+                                typ: TokenItemType::Comment,
+                                val: "# by empty lines".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
+                            },
+                        ]
+                    },
+                    Token {
+                        typ: ScanTokenType::Comment,
+                        items: vec![
+                            TokenItem {
+                                typ: TokenItemType::Comment,
+                                val: "# eof".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
+                            },
+                        ]
+                    },
                 ]
             ),
             (
@@ -591,8 +819,10 @@
                         items: vec![
                             TokenItem {
                                 typ: TokenItemType::Error,
-                                val: "config: invalid, expected keyword (package, config, option, list) or eof".to_string(),
-                                pos: 0
+                                val: "config: invalid:2:1: expected keyword (package, config, option, list) or eof".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     },
@@ -607,8 +837,10 @@
                         items: vec![
                             TokenItem {
                                 typ: TokenItemType::Error,
-                                val: "config: pkg invalid, incomplete package name".to_string(),
-                                pos: 0
+                                val: "config: pkg invalid:2:9: incomplete package name".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     }
@@ -624,7 +856,9 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "foo".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -633,8 +867,10 @@
                         items: vec![
                             TokenItem {
                                 typ: TokenItemType::Error,
-                                val: "config: unterminated quoted string, unterminated quoted string".to_string(),
-                                pos: 0
+                                val: "config: unterminated quoted string:2:16: unterminated quoted string".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             }
                         ]
                     }
@@ -650,7 +886,9 @@
                             TokenItem {
                                 typ: TokenItemType::Ident,
                                 val: "foo".to_string(),
-                                pos: 0
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     },
@@ -659,8 +897,10 @@
                         items: vec![
                             TokenItem {
                                 typ: TokenItemType::Error,
-                                val: "config: unterminated unquoted string, unterminated unquoted string".to_string(),
-                                pos: 0
+                                val: "config: unterminated unquoted string:4:1: unterminated unquoted string".to_string(),
+                                pos: 0,
+                                line: 0,
+                                col: 0
                             },
                         ]
                     }
@@ -687,4 +927,142 @@
 
             assert_eq!(expected.len(), idx);
         }
-    }
\ No newline at end of file
+    }
+
+#[test]
+fn test_parse_error_location() {
+    let err = uci_parse("network", "config foo\n\toption opt 'val\n".to_string()).unwrap_err();
+    assert_eq!(
+        err.message,
+        "parse error: config: network:2:17: unterminated quoted string"
+    );
+    assert_eq!((err.line, err.col), (Some(2), Some(17)));
+}
+
+#[test]
+fn test_parse_error_span() {
+    let input = "config foo\n\toption opt 'val\n";
+    let err = uci_parse("network", input.to_string()).unwrap_err();
+    assert_eq!(err.pos, Some(27));
+    assert_eq!(err.span, Some((23, 27)));
+    assert_eq!(&input[23..27], "'val");
+
+    let input = "config foo 'bar'\n\toption 'opt' val\n";
+    let err = uci_parse("network", input.to_string()).unwrap_err();
+    assert_eq!(err.message, "parse error: expected option name");
+    let (start, end) = err.span.unwrap();
+    assert_eq!(err.pos, Some(start));
+    assert_eq!(&input[start..end], "'opt'");
+
+    let input = "config foo\n\tlist\n";
+    let err = uci_parse("network", input.to_string()).unwrap_err();
+    assert_eq!(err.span, Some((17, 17)));
+}
+
+#[test]
+fn test_parse_strict_duplicate_section() {
+    let input = "config interface 'lan'\n\toption proto 'static'\n\nconfig interface 'lan'\n\toption mtu '1400'\n";
+    let err = uci_parse_strict("network", input.to_string()).unwrap_err();
+    assert_eq!(err.message, "parse error: duplicate section 'lan'");
+
+    let cfg = uci_parse("network", input.to_string()).unwrap();
+    assert_eq!(cfg.sections.len(), 1);
+
+    let input = "config rule\nconfig rule\nconfig interface 'lan'\nconfig interface 'wan'\n";
+    let cfg = uci_parse_strict("network", input.to_string()).unwrap();
+    assert_eq!(cfg.sections.len(), 4);
+}
+
+#[test]
+fn test_parse_empty_option() {
+    let input = "config foo 'bar'\n\toption empty\n\toption next 'val'\n\toption last\n";
+    let err = uci_parse("network", input.to_string()).unwrap_err();
+    assert_eq!(err.message, "parse error: expected option value");
+
+    let opts = ParseOptions {
+        allow_empty_option: true,
+        ..ParseOptions::default()
+    };
+    let cfg = uci_parse_with("network", input.to_string(), opts).unwrap();
+    let sec = cfg.get("bar").unwrap().unwrap();
+    assert_eq!(sec.get("empty").unwrap().values, vec![String::new()]);
+    assert_eq!(sec.get("next").unwrap().values, vec!["val".to_string()]);
+    assert_eq!(sec.get("last").unwrap().values, vec![String::new()]);
+
+    let err = uci_parse_with("network", "config foo\n\tlist empty\n".to_string(), opts);
+    assert!(err.is_err());
+
+    let input = "config foo 'bar'\n\toption eof";
+    let cfg = uci_parse_with("network", input.to_string(), opts).unwrap();
+    let sec = cfg.get("bar").unwrap().unwrap();
+    assert_eq!(sec.get("eof").unwrap().values, vec![String::new()]);
+    let err = uci_parse("network", input.to_string()).unwrap_err();
+    assert_eq!(err.message, "parse error: expected option value");
+}
+
+#[test]
+fn test_parse_quoted_section_type() {
+    let input =
+        "config 'interface' 'lan'\n\toption proto 'static'\nconfig \"rule\"\n\toption name 'a'\n";
+    let cfg = uci_parse("network", input.to_string()).unwrap();
+    let lan = cfg.get("lan").unwrap().unwrap();
+    assert_eq!(lan.sec_type, "interface");
+    assert_eq!(lan.get("proto").unwrap().values, vec!["static".to_string()]);
+    let rule = cfg.get("@rule[0]").unwrap().unwrap();
+    assert_eq!(rule.get("name").unwrap().values, vec!["a".to_string()]);
+
+    let err = uci_parse("network", "config 'interface\n".to_string()).unwrap_err();
+    assert_eq!(
+        err.message,
+        "parse error: config: network:1:18: unterminated quoted string"
+    );
+
+    let err = uci_parse("network", "config 'my type' 'lan'\n".to_string()).unwrap_err();
+    assert_eq!(
+        err.message,
+        "parse error: config: network:1:11: invalid section type: only [A-Za-z0-9_-] is allowed"
+    );
+
+    let err = uci_parse("network", "config '' 'x'\n".to_string()).unwrap_err();
+    assert_eq!(
+        err.message,
+        "parse error: config: network:1:9: invalid section type: only [A-Za-z0-9_-] is allowed"
+    );
+}
+
+#[test]
+fn test_parse_skip_leading_until_keyword() {
+    let input = "#!/bin/sh template\n{{ header }}\n\nconfig foo 'bar'\n\toption a 'b'\n";
+    let err = uci_parse("network", input.to_string()).unwrap_err();
+    assert_eq!(err.line, Some(2));
+
+    let opts = ParseOptions {
+        skip_leading_until_keyword: true,
+        ..ParseOptions::default()
+    };
+    let cfg = uci_parse_with("network", input.to_string(), opts).unwrap();
+    assert_eq!(cfg.len(), 1);
+    assert!(cfg.header_comments.is_empty());
+    let sec = cfg.get("bar").unwrap().unwrap();
+    assert_eq!(sec.comments, vec!["#!/bin/sh template".to_string()]);
+    assert_eq!(sec.get("a").unwrap().values, vec!["b".to_string()]);
+
+    let input = "{{ header }}\npackage 'net'\nconfig foo\n\toption a 'b\n";
+    let err = uci_parse_with("network", input.to_string(), opts).unwrap_err();
+    assert_eq!(err.line, Some(4));
+    assert_eq!(err.pos, Some(input.len() - 1));
+
+    let cfg = uci_parse_with("network", "{{ header }}\n".to_string(), opts).unwrap();
+    assert!(!cfg.has_content());
+
+    let input = "# managed by ansible\n{{ header }}\n# lan\nconfig interface 'lan'\n";
+    let cfg = uci_parse_with("network", input.to_string(), opts).unwrap();
+    assert_eq!(
+        cfg.sections[0].comments,
+        vec!["# managed by ansible".to_string(), "# lan".to_string()]
+    );
+    assert_eq!(
+        cfg.to_string(),
+        "# managed by ansible\n# lan\nconfig interface 'lan'\n"
+    );
+}
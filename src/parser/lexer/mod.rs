@@ -9,6 +9,11 @@ pub struct Lexer {
     start: usize,
     pos: usize,
     width: usize,
+    line: usize,
+    col: usize,
+    last_col: usize,
+    start_line: usize,
+    start_col: usize,
     state: Option<LexerState>,
     items: Option<VecDeque<TokenItem>>,
 }
@@ -57,19 +62,49 @@ impl Lexer {
             start: 0,
             pos: 0,
             width: 0,
+            line: 1,
+            col: 1,
+            last_col: 1,
+            start_line: 1,
+            start_col: 1,
         }
     }
 
+    pub fn starting_at_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self.start_line = line;
+        self
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Start of the token being lexed; after a lexer error this is where the
+    /// failing token began.
+    pub fn token_start(&self) -> usize {
+        self.start
+    }
+
     fn next_rune(&mut self) -> Option<char> {
         if self.pos >= self.input.len() {
             self.width = 0;
             return None;
         };
-        if let Some(rune) = self.input.get(self.pos..).unwrap().chars().next() {
+        let rest = self.input.get(self.pos..).unwrap_or_default();
+        if let Some(rune) = rest.chars().next() {
             self.width = rune.len_utf8();
             self.pos += self.width;
+            if rune == '\n' || (rune == '\r' && !self.input[self.pos..].starts_with('\n')) {
+                self.line += 1;
+                self.last_col = self.col;
+                self.col = 1;
+            } else {
+                self.col += 1;
+            }
             Some(rune)
         } else {
+            self.width = 0;
             None
         }
     }
@@ -86,11 +121,31 @@ impl Lexer {
     }
 
     fn backup(&mut self) {
+        if self.width == 0 {
+            return;
+        }
         self.pos -= self.width;
+        self.width = 0;
+        let bytes = self.input.as_bytes();
+        if bytes[self.pos] == b'\n'
+            || (bytes[self.pos] == b'\r' && bytes.get(self.pos + 1) != Some(&b'\n'))
+        {
+            self.line -= 1;
+            self.col = self.last_col;
+        } else {
+            self.col -= 1;
+        }
+    }
+
+    fn advance(&mut self, len: usize) {
+        self.pos += len;
+        self.col += len;
     }
 
     fn ignore(&mut self) {
         self.start = self.pos;
+        self.start_line = self.line;
+        self.start_col = self.col;
     }
 
     fn peek(&mut self) -> Option<char> {
@@ -105,28 +160,42 @@ impl Lexer {
 
     fn emit(&mut self, typ: TokenItemType) {
         if self.pos > self.start {
+            let val = match self.input.get(self.start..self.pos) {
+                Some(val) => val.to_string(),
+                None => {
+                    self.emit_error("token does not fall on a character boundary");
+                    return;
+                }
+            };
             self.items.as_mut().unwrap().push_back(TokenItem {
                 typ,
-                val: self.input.get(self.start..self.pos).unwrap().to_string(),
-                pos: self.pos,
+                val,
+                pos: self.start,
+                line: self.start_line,
+                col: self.start_col,
             });
-            self.start = self.pos;
+            self.ignore();
         }
     }
 
     fn emit_error(&mut self, error: &str) -> Option<LexerState> {
         self.items.as_mut().unwrap().push_back(TokenItem {
             typ: TokenItemType::Error,
-            val: format!("config: {}, {}", self.name, error),
+            val: format!(
+                "config: {}:{}:{}: {}",
+                self.name, self.line, self.col, error
+            ),
             pos: self.pos,
+            line: self.line,
+            col: self.col,
         });
         None
     }
 
     fn accept_comment(&mut self) {
-        if self.next_rune().unwrap() == '#' {
+        if self.next_rune() == Some('#') {
            while let Some(r) = self.next_rune() {
-                    if r == '\n' {
+                    if r == '\n' || r == '\r' {
                         break;
                     }
                 } 
@@ -165,13 +234,14 @@ impl Lexer {
                 Some(r)
                     if !(r == '-'
                         || r == '_'
-                        || ('a'..='z').contains(&r)
-                        || ('A'..='Z').contains(&r)
-                        || ('0'..='9').contains(&r)) =>
+                        || r.is_ascii_lowercase()
+                        || r.is_ascii_uppercase()
+                        || r.is_ascii_digit()) =>
                 {
                     self.backup();
                     break;
                 }
+                None => break,
                 _ => {}
             }
         }
@@ -214,27 +284,41 @@ impl Lexer {
     }
 
     fn eof(&self) -> TokenItem {
-        return TokenItem {
+        let val = self.input.get(self.start..self.pos).unwrap_or_default();
+        TokenItem {
             typ: TokenItemType::Eof,
-            val: self.input.get(self.start..self.pos).unwrap().to_string(),
+            val: val.to_string(),
             pos: self.pos,
-        };
+            line: self.start_line,
+            col: self.start_col,
+        }
     }
 
     fn emit_string(&mut self, t: TokenItemType) {
         if self.pos > self.start + 1 {
+            let raw = match self.input.get(self.start + 1..self.pos - 1) {
+                Some(raw) => raw,
+                None => {
+                    self.emit_error("token does not fall on a character boundary");
+                    return;
+                }
+            };
+            let val = if self.input.as_bytes()[self.start] == b'"' {
+                unescape_double_quoted(raw)
+            } else {
+                raw.to_string()
+            };
             self.items.as_mut().unwrap().push_back(TokenItem {
                 typ: t,
-                val: self
-                    .input
-                    .get(self.start + 1..self.pos - 1)
-                    .unwrap()
-                    .to_string(),
-                pos: self.pos,
+                val,
+                pos: self.start,
+                line: self.start_line,
+                col: self.start_col,
             });
-            self.start = self.pos;
+            self.ignore();
         };
     }
+
 }
 
 impl LexerStateMachine for Lexer {
@@ -268,6 +352,7 @@ impl LexerStateMachine for Lexer {
                 if self.next_rune().is_none() {
                     self.emit(TokenItemType::Eof);
                 } else {
+                    self.backup();
                     self.emit_error("expected keyword (package, config, option, list) or eof");
                 }
                 None
@@ -277,12 +362,12 @@ impl LexerStateMachine for Lexer {
 
     fn lex_comment(&mut self) -> Option<LexerState> {
         self.accept_comment();
-        self.ignore();
+        self.emit(TokenItemType::Comment);
         Some(LexerState::KeyWord)
     }
 
     fn lex_package(&mut self) -> Option<LexerState> {
-        self.pos += KeyWord::KW_PACKAGE.len();
+        self.advance(KeyWord::KW_PACKAGE.len());
         self.emit(TokenItemType::Package);
         Some(LexerState::PackageName)
     }
@@ -290,7 +375,10 @@ impl LexerStateMachine for Lexer {
     fn lex_package_name(&mut self) -> Option<LexerState> {
         loop {
             match self.next_rune() {
-                Some(r) if r == '\n' => return self.emit_error("incomplete package name"),
+                Some(r) if r == '\n' || r == '\r' => {
+                    self.backup();
+                    return self.emit_error("incomplete package name");
+                }
                 Some(r) if r.is_whitespace() => {
                     self.ignore();
                 }
@@ -298,31 +386,56 @@ impl LexerStateMachine for Lexer {
                     self.backup();
                     return Some(LexerState::Quoted);
                 }
+                None => return self.emit_error("incomplete package name"),
                 _ => {}
             }
         }
     }
     fn lex_config(&mut self) -> Option<LexerState> {
-        self.pos += KeyWord::KW_CONFIG.len();
+        self.advance(KeyWord::KW_CONFIG.len());
         self.emit(TokenItemType::Config);
         self.consume_nowrap_whitespace();
         Some(LexerState::ConfigType)
     }
 
     fn lex_config_type(&mut self) -> Option<LexerState> {
+        let quote = match self.peek() {
+            Some(r) if r == '"' || r == '\'' => self.next_rune(),
+            _ => None,
+        };
         self.accept_ident();
-        self.emit(TokenItemType::Ident);
+        match quote {
+            Some(q) => match self.next_rune() {
+                Some(r) if r == q && self.pos > self.start + 2 => {
+                    self.emit_string(TokenItemType::Ident)
+                }
+                Some(r) if r == '\n' || r == '\r' => {
+                    self.backup();
+                    return self.emit_error("unterminated quoted string");
+                }
+                Some(_) => {
+                    self.backup();
+                    return self.emit_error("invalid section type: only [A-Za-z0-9_-] is allowed");
+                }
+                None => return self.emit_error("unterminated quoted string"),
+            },
+            None => self.emit(TokenItemType::Ident),
+        }
         self.consume_nowrap_whitespace();
         Some(LexerState::OptionalName)
     }
 
     fn lex_optional_name(&mut self) -> Option<LexerState> {
         match self.next_rune() {
-            Some(r) if r == '\n' => self.ignore(),
+            Some(r) if r == '\n' || r == '\r' => self.ignore(),
             Some(r) if r == '"' || r == '\'' => {
                 self.backup();
                 return Some(LexerState::Quoted);
             }
+            Some('#') => {
+                self.backup();
+                return Some(LexerState::Comment);
+            }
             _ => {
                 self.accept_ident();
                 self.emit(TokenItemType::String)
@@ -332,14 +445,14 @@ impl LexerStateMachine for Lexer {
     }
 
     fn lex_option(&mut self) -> Option<LexerState> {
-        self.pos += KeyWord::KW_OPTION.len();
+        self.advance(KeyWord::KW_OPTION.len());
         self.emit(TokenItemType::Option);
         self.consume_nowrap_whitespace();
         Some(LexerState::OptionName)
     }
 
     fn lex_list(&mut self) -> Option<LexerState> {
-        self.pos += KeyWord::KW_LIST.len();
+        self.advance(KeyWord::KW_LIST.len());
         self.emit(TokenItemType::List);
         self.consume_nowrap_whitespace();
         Some(LexerState::OptionName)
@@ -369,12 +482,20 @@ impl LexerStateMachine for Lexer {
             loop {
                 match self.next_rune() {
                     Some(r) if r == '\\' => {
-                        if self.next_rune().is_some() {
+                        // single quotes are literal apart from line continuations
+                        if q == '\'' && !matches!(self.peek(), Some('\n') | Some('\r')) {
+                            continue;
+                        }
+                        if let Some(r) = self.next_rune() {
+                            if r == '\r' {
+                                self.accept_once("\n");
+                            }
                         } else {
                             return self.emit_error("unterminated quoted string");
                         };
                     }
-                    Some(r) if r == '\n' => {
+                    Some(r) if r == '\n' || r == '\r' => {
+                        self.backup();
                         return self.emit_error("unterminated quoted string");
                     }
                     None => {
@@ -397,14 +518,19 @@ impl LexerStateMachine for Lexer {
         loop {
             match self.next_rune() {
                 Some(r) if r == '\\' => {
-                    if self.next_rune().is_none() {
-                        return self.emit_error("unterminated unquoted string");
+                    match self.next_rune() {
+                        Some(r) if r == '\r' => {
+                            self.accept_once("\n");
+                        }
+                        Some(_) => {}
+                        None => return self.emit_error("unterminated unquoted string"),
                     };
                 }
+                None if self.pos == self.start => break,
                 None => {
                     return self.emit_error("unterminated unquoted string");
                 }
-                Some(r) if r == ' ' || r == '\t' || r == '#' || r == '\n' => {
+                Some(r) if r == ' ' || r == '\t' || r == '#' || r == '\n' || r == '\r' => {
                     break;
                 }
                 Some(_) => {}
@@ -413,12 +539,36 @@ impl LexerStateMachine for Lexer {
         self.backup();
         self.emit(TokenItemType::String);
         self.consume_nowrap_whitespace();
+        self.accept_once("\r");
         self.accept_once("\n");
         self.ignore();
         Some(LexerState::KeyWord)
     }
 }
 
+fn unescape_double_quoted(raw: &str) -> String {
+    let mut val = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            val.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => val.push('"'),
+            Some('\\') => val.push('\\'),
+            Some('n') => val.push('\n'),
+            Some('r') => val.push('\r'),
+            Some('t') => val.push('\t'),
+            Some(other) => {
+                val.push('\\');
+                val.push(other);
+            }
+            None => val.push('\\'),
+        }
+    }
+    val
+}
 
 #[cfg(test)]
 mod test;
\ No newline at end of file
@@ -21,31 +21,43 @@ fn test_lexer() {
                         typ: TokenItemType::Config,
                         val: "config".to_string(),
                         pos: 0,
+                        line: 0,
+                        col: 0,
                      },
                     TokenItem {
                         typ: TokenItemType::Ident,
                         val: "sectiontype".to_string(),
                         pos: 0,
+                        line: 0,
+                        col: 0,
                     },
                     TokenItem {
                         typ: TokenItemType::String,
                         val: "sectionname".to_string(),
                         pos: 0,
+                        line: 0,
+                        col: 0,
                     },
                     TokenItem {
                         typ: TokenItemType::Option,
                         val: "option".to_string(),
                         pos: 0,
+                        line: 0,
+                        col: 0,
                     },
                     TokenItem {
                         typ: TokenItemType::Ident,
                         val: "optionname".to_string(),
                         pos: 0,
+                        line: 0,
+                        col: 0,
                     },
                     TokenItem {
                         typ: TokenItemType::String,
                         val: "optionvalue".to_string(),
                         pos: 0,
+                        line: 0,
+                        col: 0,
                     },
                 ],
             ),
@@ -56,67 +68,93 @@ fn test_lexer() {
                     TokenItem {
                         typ: TokenItemType::Package, 
                         val: "package".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "pkgname".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "empty".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "squoted".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "sqname".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "dquoted".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "dqname".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "multiline".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "line1\\\n\tline2".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                 ]
             ),
@@ -127,32 +165,44 @@ fn test_lexer() {
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "foo".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "bar".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "answer".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "42".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                 ]
             ),
@@ -163,235 +213,327 @@ fn test_lexer() {
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "foo".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "named".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "pos".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "0".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "unnamed".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "0".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::List, 
                         val: "list".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "list".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "0".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
 
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "foo".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "pos".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "1".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "unnamed".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "1".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::List, 
                         val: "list".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "list".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "10".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
 
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "foo".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "pos".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "2".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "unnamed".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "1".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::List, 
                         val: "list".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "list".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "20".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
 
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "foo".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "named".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "pos".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "3".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "unnamed".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "0".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::List, 
                         val: "list".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "list".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "30".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                 ]
             ),
@@ -402,92 +544,128 @@ fn test_lexer() {
             	    TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "wifi-device".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "wl0".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "type".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "broadcom".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "channel".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "6".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "wifi-iface".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "wifi0".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "device".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "wl0".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "mode".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "ap".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     }
                 ]
             ),
@@ -495,61 +673,139 @@ fn test_lexer() {
                 "commented", 
                 "\n# heading\n\n# another heading\nconfig foo\n\toption opt1 1\n\t# option opt1 2\n\toption opt2 3 # baa\n\toption opt3 hello\n\n# a comment block spanning\n# multiple lines, surrounded\n# by empty lines\n\n# eof\n".to_string(), 
                 vec![
+                    TokenItem {
+                        typ: TokenItemType::Comment, 
+                        val: "# heading".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
+                    TokenItem {
+                        typ: TokenItemType::Comment, 
+                        val: "# another heading".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
                     TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "foo".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "opt1".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "1".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
+                    TokenItem {
+                        typ: TokenItemType::Comment, 
+                        val: "# option opt1 2".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "opt2".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "3".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
+                    TokenItem {
+                        typ: TokenItemType::Comment, 
+                        val: "# baa".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "opt3".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::String, 
                         val: "hello".to_string(), 
-                        pos: 0
-                    }
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
+                    TokenItem {
+                        typ: TokenItemType::Comment, 
+                        val: "# a comment block spanning".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
+                    TokenItem {
+                        typ: TokenItemType::Comment, 
+                        val: "# multiple lines, surrounded".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
+                    TokenItem {
+                        typ: TokenItemType::Comment, 
+                        val: "# by empty lines".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
+                    TokenItem {
+                        typ: TokenItemType::Comment, 
+                        val: "# eof".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
+                    },
                 ]
             ),
             (
@@ -558,8 +814,10 @@ fn test_lexer() {
                 vec![
                     TokenItem {
                         typ: TokenItemType::Error, 
-                        val: "config: invalid, expected keyword (package, config, option, list) or eof".to_string(), 
-                        pos: 0
+                        val: "config: invalid:2:1: expected keyword (package, config, option, list) or eof".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     }
                 ],
             ),
@@ -570,12 +828,16 @@ fn test_lexer() {
                     TokenItem {
                         typ: TokenItemType::Package, 
                         val: "package".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Error, 
-                        val: "config: pkg invalid, incomplete package name".to_string(), 
-                        pos: 0
+                        val: "config: pkg invalid:2:9: incomplete package name".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                 ],
             ),
@@ -586,17 +848,23 @@ fn test_lexer() {
             		TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "foo".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Error, 
-                        val: "config: unterminated quoted string, unterminated quoted string".to_string(), 
-                        pos: 0
+                        val: "config: unterminated quoted string:2:16: unterminated quoted string".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
             	]
             ),
@@ -607,27 +875,37 @@ fn test_lexer() {
             		TokenItem {
                         typ: TokenItemType::Config, 
                         val: "config".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "foo".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Option, 
                         val: "option".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Ident, 
                         val: "opt".to_string(), 
-                        pos: 0
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
                     TokenItem {
                         typ: TokenItemType::Error, 
-                        val: "config: unterminated unquoted string, unterminated unquoted string".to_string(), 
-                        pos: 0
+                        val: "config: unterminated unquoted string:4:1: unterminated unquoted string".to_string(), 
+                        pos: 0,
+                        line: 0,
+                        col: 0
                     },
             	]
             ),
@@ -650,3 +928,118 @@ fn test_lexer() {
             assert_eq!(expected.len(), idx);
         }
     }
+
+#[test]
+fn test_lexer_line_col() {
+    let mut lex = Lexer::new(
+        "line col",
+        "config foo 'bar'\n\toption opt 'val'\n".to_string(),
+    );
+    let expected = vec![
+        (TokenItemType::Config, 1, 1),
+        (TokenItemType::Ident, 1, 8),
+        (TokenItemType::String, 1, 12),
+        (TokenItemType::Option, 2, 2),
+        (TokenItemType::Ident, 2, 9),
+        (TokenItemType::String, 2, 13),
+    ];
+    for (typ, line, col) in expected {
+        let item = lex.next_item();
+        assert_eq!((item.typ, item.line, item.col), (typ, line, col));
+    }
+    assert_eq!(lex.next_item().typ, TokenItemType::Eof);
+}
+
+#[test]
+fn test_lexer_quoted_escapes() {
+    let test_cases = vec![
+        (r#"option x "a\"b""#, Ok(r#"a"b"#)),
+        (r#"option x "a\\b""#, Ok(r#"a\b"#)),
+        (r#"option x "a\nb""#, Ok("a\nb")),
+        (r#"option x "a\tb""#, Ok("a\tb")),
+        (r#"option x "a\rb""#, Ok("a\rb")),
+        (r#"option x "a\qb""#, Ok(r#"a\qb"#)),
+        (r#"option x 'a\"b'"#, Ok(r#"a\"b"#)),
+        (r#"option x 'a\nb'"#, Ok(r#"a\nb"#)),
+        (r#"option x 'C:\'"#, Ok(r#"C:\"#)),
+        (
+            r#"option x "a\"#,
+            Err("config: escape:1:13: unterminated quoted string"),
+        ),
+    ];
+    for (input, expected) in test_cases {
+        let mut lex = Lexer::new("escape", input.to_string());
+        assert_eq!(lex.next_item().typ, TokenItemType::Option);
+        assert_eq!(lex.next_item().typ, TokenItemType::Ident);
+        let item = lex.next_item();
+        match expected {
+            Ok(val) => {
+                assert_eq!(item.typ, TokenItemType::String);
+                assert_eq!(item.val, val);
+            }
+            Err(err) => {
+                assert_eq!(item.typ, TokenItemType::Error);
+                assert_eq!(item.val, err);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_lexer_crlf() {
+    let test_cases = vec![
+        "config sectiontype 'sectionname' \r\n\t option optionname 'optionvalue'\r\n",
+        "config sectiontype sectionname\r\n\toption optionname optionvalue\r\n",
+        "config sectiontype 'sectionname'\r\toption optionname optionvalue\r",
+        "# comment\r\nconfig sectiontype \"sectionname\"\r\n\toption optionname optionvalue\r\n",
+    ];
+    for input in test_cases {
+        let mut lex = Lexer::new("crlf", input.to_string());
+        let mut values = vec![];
+        loop {
+            let item = lex.next_item();
+            match item.typ {
+                TokenItemType::Eof => break,
+                TokenItemType::Comment => assert_eq!(item.val, "# comment"),
+                TokenItemType::Error => panic!("{}", item.val),
+                _ => values.push(item.val),
+            }
+        }
+        assert_eq!(
+            values,
+            vec!["config", "sectiontype", "sectionname", "option", "optionname", "optionvalue"]
+        );
+    }
+
+    let mut lex = Lexer::new("crlf", "config foo\rconfig bar\r\n".to_string());
+    let lines: Vec<usize> = (0..4).map(|_| lex.next_item().line).collect();
+    assert_eq!(lines, vec![1, 1, 2, 2]);
+}
+
+#[test]
+fn test_lexer_adversarial_input() {
+    let inputs = [
+        "package 'net'\nconfig 'interface' \"lan\"\n\toption proto 'static' # c\n\tlist dns 1.1.1.1\n",
+        "configé optionü listß package😀 #\u{0}",
+        "config é\n\toption 😀 'ä\\\n",
+        "package\r\nconfig\r'a\\\r\n\"b\\",
+    ];
+    for input in inputs {
+        let prefixes = input
+            .char_indices()
+            .map(|(idx, _)| &input[..idx])
+            .chain(Some(input));
+        for prefix in prefixes {
+            let mut lex = Lexer::new("fuzz", prefix.to_string());
+            let mut count = 0;
+            loop {
+                let item = lex.next_item();
+                if item.typ == TokenItemType::Eof || item.typ == TokenItemType::Error {
+                    break;
+                }
+                count += 1;
+                assert!(count <= prefix.len(), "lexer did not terminate on {:?}", prefix);
+            }
+        }
+    }
+}
@@ -4,5 +4,14 @@ mod lexer;
 mod token;
 mod imp;
 
+pub use self::imp::parse_streaming;
 pub use self::imp::uci_parse;
-pub use self::imp::parse_raw_to_uci;
\ No newline at end of file
+pub use self::imp::uci_parse_with;
+pub use self::imp::uci_parse_collect;
+pub use self::imp::uci_parse_strict;
+pub use self::imp::parse_raw_to_uci;
+pub use self::imp::parse_raw_to_uci_with;
+pub use self::imp::ParseOptions;
+pub use self::imp::tokenize;
+pub(crate) use self::imp::uci_parse_section;
+pub use self::token::{ScanTokenType, Token, TokenItem, TokenItemType};
\ No newline at end of file
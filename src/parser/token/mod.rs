@@ -5,6 +5,8 @@ pub struct TokenItem {
     pub typ: TokenItemType,
     pub val: String,
     pub pos: usize,
+    pub line: usize,
+    pub col: usize,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -17,6 +19,7 @@ pub enum TokenItemType {
    List,
    Ident,
    String,
+   Comment,
 }
 
 impl fmt::Display for TokenItemType {
@@ -46,6 +49,9 @@ impl fmt::Display for TokenItemType {
             Self::String => {
                 write!(f, "String")
             }
+            Self::Comment => {
+                write!(f, "Comment")
+            }
         }
     }
 }
@@ -81,6 +87,7 @@ pub enum ScanTokenType {
     Section,
     Option,
     List,
+    Comment,
 }
 
 impl fmt::Display for ScanTokenType {
@@ -101,6 +108,9 @@ impl fmt::Display for ScanTokenType {
             Self::Section => {
                 write!(f, "config")
             }
+            Self::Comment => {
+                write!(f, "comment")
+            }
         }
     }
 }
@@ -9,6 +9,7 @@ fn test_token_item_type_to_string() {
         assert_eq!(TokenItemType::Option.to_string(), "Option");
         assert_eq!(TokenItemType::Package.to_string(), "Package");
         assert_eq!(TokenItemType::String.to_string(), "String");
+        assert_eq!(TokenItemType::Comment.to_string(), "Comment");
     }
 #[test]
 fn test_scan_token_to_string() {
@@ -17,6 +18,7 @@ fn test_scan_token_to_string() {
         assert_eq!(ScanTokenType::Option.to_string(), "option");
         assert_eq!(ScanTokenType::Package.to_string(), "package");
         assert_eq!(ScanTokenType::Section.to_string(), "config");
+        assert_eq!(ScanTokenType::Comment.to_string(), "comment");
     }
 #[test]
 fn test_token_item_to_string() {
@@ -24,6 +26,8 @@ fn test_token_item_to_string() {
             typ: TokenItemType::Option,
             val: "network wlan".to_string(),
             pos: 0,
+            line: 0,
+            col: 0,
         };
         assert_eq!(token_item.to_string(), "(Option \"network wlan\" 0)");
     }
@@ -36,10 +40,12 @@ fn test_token_to_string() {
                 typ: TokenItemType::Ident,
                 val: "network".to_string(),
                 pos: 0,
+                line: 0,
+                col: 0,
             }],
         };
         assert_eq!(
             token.to_string(),
-            "package [TokenItem { typ: Ident, val: \"network\", pos: 0 }]"
+            "package [TokenItem { typ: Ident, val: \"network\", pos: 0, line: 0, col: 0 }]"
         );
     }
\ No newline at end of file
@@ -1,94 +1,332 @@
-use std::str::from_utf8;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::{Mutex, MutexGuard};
 
+use super::uci_option::{UciOptionType, UciQuoteStyle};
 use super::uci_section::UciSection;
-use crate::utils::{Error, Result};
+use crate::utils::{compact_output, Error, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub indent: String,
+    pub quote: char,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            indent: "\t".to_string(),
+            quote: '\'',
+        }
+    }
+}
+
+/// Positions of the sections of each type, built on first use. Mutators that
+/// can reorder sections or change a type reset it; lookups also rebuild it when
+/// a hit no longer matches the sections.
+#[derive(Default)]
+struct TypeIndex(Mutex<Option<HashMap<String, Vec<usize>>>>);
+
+impl TypeIndex {
+    fn lock(&self) -> MutexGuard<'_, Option<HashMap<String, Vec<usize>>>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn invalidate(&mut self) {
+        *self.0.get_mut().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+}
+
+impl Clone for TypeIndex {
+    fn clone(&self) -> Self {
+        TypeIndex::default()
+    }
+}
+
+impl fmt::Debug for TypeIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TypeIndex")
+    }
+}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UciConfig {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pkg_name: String,
     pub sections: Vec<UciSection>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub modified: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub trailing_comments: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub header_comments: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    source_edges: Option<(String, String)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    type_index: TypeIndex,
 }
 
 impl UciConfig {
     pub fn new(name: &str) -> UciConfig {
         UciConfig {
             name: name.to_owned(),
+            pkg_name: String::new(),
             sections: Vec::new(),
             modified: false,
+            trailing_comments: Vec::new(),
+            header_comments: Vec::new(),
+            source_edges: None,
+            type_index: TypeIndex::default(),
         }
     }
 
+    /// Runs `f` against the positions of `sec_type`. `f` returns `None` when
+    /// the positions no longer match the sections, and is then retried once
+    /// against a freshly built index.
+    fn _with_positions<R, F>(&self, sec_type: &str, f: F) -> R
+    where
+        F: Fn(&[usize]) -> Option<R>,
+    {
+        let mut index = self.type_index.lock();
+        if let Some(types) = index.as_ref() {
+            if types.values().map(Vec::len).sum::<usize>() == self.sections.len() {
+                let positions = types.get(sec_type).map_or(&[][..], Vec::as_slice);
+                if let Some(found) = f(positions) {
+                    return found;
+                }
+            }
+        }
+        let mut types: HashMap<String, Vec<usize>> = HashMap::new();
+        for (pos, sec) in self.sections.iter().enumerate() {
+            types.entry(sec.sec_type.clone()).or_default().push(pos);
+        }
+        let found = f(types.get(sec_type).map_or(&[][..], Vec::as_slice));
+        *index = Some(types);
+        found.expect("a freshly built type index matches the sections")
+    }
+
+    fn _is_of_type(&self, pos: usize, sec_type: &str) -> bool {
+        self.sections
+            .get(pos)
+            .map_or(false, |sec| sec.sec_type == sec_type)
+    }
+
+    fn _nth_of_type(&self, sec_type: &str, index: usize) -> Option<usize> {
+        self._with_positions(sec_type, |positions| match positions.get(index) {
+            Some(&pos) if !self._is_of_type(pos, sec_type) => None,
+            found => Some(found.copied()),
+        })
+    }
+
+    /// Position of `section` when it is borrowed from `self.sections`.
+    fn _position_of(&self, section: &UciSection) -> Option<usize> {
+        let size = std::mem::size_of::<UciSection>();
+        let offset =
+            (section as *const UciSection as usize).checked_sub(self.sections.as_ptr() as usize)?;
+        let pos = offset / size;
+        (offset % size == 0 && pos < self.sections.len()).then_some(pos)
+    }
+
     fn _index(&self, section: &UciSection) -> Option<usize> {
+        if let Some(pos) = self._position_of(section) {
+            return self._with_positions(&section.sec_type, |positions| {
+                positions.binary_search(&pos).ok().map(Some)
+            });
+        }
         self.sections
             .iter()
             .filter(|sec| sec.sec_type == section.sec_type)
             .position(|sec| sec == section)
     }
 
+    pub(crate) fn invalidate_index(&mut self) {
+        self.type_index.invalidate();
+    }
+
     fn _get_named(&self, name: &str) -> Option<&UciSection> {
         self.sections.iter().find(|section| section.name == name)
     }
 
     fn _get_named_mut(&mut self, name: &str) -> Option<&mut UciSection> {
+        self.invalidate_index();
         self.sections
             .iter_mut()
             .find(|section| section.name == name)
     }
 
-    fn _get_unnamed(&self, name: &str) -> Result<Option<&UciSection>> {
-        let (sec_type, sec_index) = unmangle_section_name(name)?;
-        let count = self._count(&sec_type);
+    fn _resolve_index(&self, sec_type: &str, sec_index: i32) -> Result<usize> {
+        self._checked_index(sec_type, sec_index)
+            .ok_or_else(|| Error::not_found("invalid name: index out of bounds"))
+    }
+
+    fn _checked_index(&self, sec_type: &str, sec_index: i32) -> Option<usize> {
+        let count = self._count(sec_type);
         let index = if sec_index >= 0 {
-            sec_index as i32
+            sec_index
         } else {
             count as i32 + sec_index
         };
 
         if index < 0 || index >= count as i32 {
-            return Err(Error::new("invalid name: index out of bounds"));
+            return None;
         };
 
-        let section = self
-            .sections
-            .iter()
-            .filter(|sec| sec.sec_type == sec_type)
-            .nth(index as usize);
+        Some(index as usize)
+    }
 
-        Ok(section)
+    pub(crate) fn find(&self, name: &str) -> Option<&UciSection> {
+        if !name.starts_with('@') {
+            return self._get_named(name);
+        }
+        let (sec_type, sec_index) = unmangle_section_name(name).ok()?;
+        let index = self._checked_index(&sec_type, sec_index)?;
+        self._nth_of_type(&sec_type, index)
+            .map(|pos| &self.sections[pos])
+    }
+
+    fn _get_unnamed(&self, name: &str) -> Result<Option<&UciSection>> {
+        let (sec_type, sec_index) = unmangle_section_name(name)?;
+        self.get_by_index(&sec_type, sec_index)
     }
 
     fn _get_unnamed_mut(&mut self, name: &str) -> Result<Option<&mut UciSection>> {
         let (sec_type, sec_index) = unmangle_section_name(name)?;
-        let count = self._count(&sec_type);
-        let index = if sec_index >= 0 {
-            sec_index as i32
+        let index = self._resolve_index(&sec_type, sec_index)?;
+        let pos = self._nth_of_type(&sec_type, index);
+        self.invalidate_index();
+
+        Ok(pos.map(|pos| &mut self.sections[pos]))
+    }
+
+    pub fn get_by_index(&self, sec_type: &str, index: i32) -> Result<Option<&UciSection>> {
+        let index = self._resolve_index(sec_type, index)?;
+        let pos = self._nth_of_type(sec_type, index);
+
+        Ok(pos.map(|pos| &self.sections[pos]))
+    }
+
+    fn _package_of<'a>(&'a self, section: &'a UciSection) -> &'a str {
+        if section.package.is_empty() {
+            &self.pkg_name
         } else {
-            count as i32 + sec_index
-        };
+            &section.package
+        }
+    }
 
-        if index < 0 || index >= count as i32 {
-            return Err(Error::new("invalid name: index out of bounds"));
-        };
+    fn _write_raw<W: Write, F: Fn(&UciSection) -> bool>(
+        &self,
+        buf: &mut W,
+        filter: F,
+        opts: &WriteOptions,
+    ) -> fmt::Result {
+        for comment in self.header_comments.iter() {
+            writeln!(buf, "{}", comment)?;
+        }
 
-        let section = self
-            .sections
-            .iter_mut()
-            .filter(|sec| sec.sec_type == sec_type)
-            .nth(index as usize);
+        let mut packages = vec![self.pkg_name.as_str()];
+        for sec in self.sections.iter().filter(|sec| filter(sec)) {
+            if !packages.contains(&self._package_of(sec)) {
+                packages.push(self._package_of(sec));
+            }
+        }
 
-        Ok(section)
+        for package in packages {
+            if !package.is_empty() {
+                write!(buf, "\npackage {}\n", quote_name(package, opts))?;
+            }
+            for sec in self
+                .sections
+                .iter()
+                .filter(|sec| filter(sec) && self._package_of(sec) == package)
+            {
+                _write_section(buf, sec, opts)?;
+            }
+        }
+
+        let has_body = !self.header_comments.is_empty()
+            || !self.pkg_name.is_empty()
+            || self.sections.iter().any(&filter);
+        if has_body || self.trailing_comments.is_empty() {
+            buf.write_char('\n')?;
+        }
+        for comment in self.trailing_comments.iter() {
+            writeln!(buf, "{}", comment)?;
+        }
+        Ok(())
     }
 
-    fn _count(&self, sec_type: &str) -> usize {
+    fn _render(&self, opts: &WriteOptions) -> String {
+        let mut raw = String::new();
+        self._write_raw(&mut raw, |_| true, opts).unwrap();
+        compact_output(&raw)
+    }
+
+    pub(crate) fn to_source_string(&self, opts: &WriteOptions) -> String {
+        let raw = self._render(opts);
+        match &self.source_edges {
+            Some((leading, _)) if raw.trim().is_empty() => leading.clone(),
+            Some((leading, trailing)) => format!("{}{}{}", leading, raw.trim(), trailing),
+            None => raw,
+        }
+    }
+
+    pub(crate) fn to_string_of_types(&self, types: &[&str]) -> String {
+        let mut raw = String::new();
+        let keep = |sec: &UciSection| types.contains(&sec.sec_type.as_str());
+        self._write_raw(&mut raw, keep, &WriteOptions::default())
+            .unwrap();
+        compact_output(&raw)
+    }
+
+    pub fn sections_by_type(&self, typ: &str) -> Vec<&UciSection> {
         self.sections
             .iter()
-            .filter(|sec| sec.sec_type == sec_type)
-            .count()
+            .filter(|sec| sec.sec_type == typ)
+            .collect()
+    }
+
+    fn _count(&self, sec_type: &str) -> usize {
+        self._with_positions(sec_type, |positions| match positions.last() {
+            Some(&pos) if !self._is_of_type(pos, sec_type) => None,
+            _ => Some(positions.len()),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.sections.len()
     }
 
-    pub(crate) fn set_name(&mut self, name: &str) {
-        self.name = name.into();
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Returns true if the config declares a package or any section; comments
+    /// and blank lines alone are not content.
+    pub fn has_content(&self) -> bool {
+        !self.pkg_name.is_empty() || !self.sections.is_empty()
+    }
+
+    pub(crate) fn set_pkg_name(&mut self, pkg_name: &str) {
+        self.pkg_name = pkg_name.into();
+    }
+
+    /// Remembers the whitespace around the parsed source so that writing the
+    /// config back reproduces its leading and trailing blank lines.
+    pub(crate) fn set_source_edges(&mut self, input: &str) {
+        // The body is always written with `\n`, so CRLF edges are normalized
+        // to keep the output's line endings consistent.
+        let normalize = |edge: &str| edge.replace("\r\n", "\n").replace('\r', "\n");
+        let leading = &input[..input.len() - input.trim_start().len()];
+        let trailing = &input[input.trim_end().len()..];
+        self.source_edges = Some((normalize(leading), normalize(trailing)));
+    }
+
+    /// Forgets the remembered source whitespace so the config is written in the
+    /// canonical layout again.
+    pub(crate) fn clear_source_edges(&mut self) {
+        self.source_edges = None;
     }
 
     pub fn get_section_name(&self, section: &UciSection) -> String {
@@ -114,21 +352,50 @@ impl UciConfig {
         }
     }
 
+    pub fn position(&self, name: &str) -> Result<Option<usize>> {
+        match self.get(name)? {
+            Some(section) => Ok(self._position_of(section)),
+            None => Ok(None),
+        }
+    }
+
     pub fn add(&mut self, section: UciSection) -> &mut UciSection {
+        self.invalidate_index();
         self.sections.push(section);
         self.sections.last_mut().unwrap()
     }
 
-    pub fn merge(&mut self, section: UciSection) -> &mut UciSection {
-        if self
+    /// Like `add_section`, a named section of a different type is replaced by
+    /// a new, empty section of `sec_type`.
+    pub fn get_or_insert_section(&mut self, sec_type: &str, name: &str) -> &mut UciSection {
+        let found = self
             .sections
             .iter()
-            .any(|sec| self.get_section_name(&section) == self.get_section_name(sec))
-        {
-            let same_name_sec_mut = self
-                .get_mut(self.get_section_name(&section).as_str())
-                .unwrap()
-                .unwrap();
+            .position(|sec| !name.is_empty() && sec.name == name);
+        match found {
+            Some(pos) if self.sections[pos].sec_type == sec_type => {
+                self.invalidate_index();
+                &mut self.sections[pos]
+            }
+            Some(pos) => {
+                self.invalidate_index();
+                self.sections.remove(pos);
+                self.add(UciSection::new(sec_type, name))
+            }
+            None => self.add(UciSection::new(sec_type, name)),
+        }
+    }
+
+    pub fn prepend(&mut self, section: UciSection) -> &mut UciSection {
+        self.invalidate_index();
+        self.sections.insert(0, section);
+        self.sections.first_mut().unwrap()
+    }
+
+    pub fn merge(&mut self, section: UciSection) -> &mut UciSection {
+        let name = self.get_section_name(&section);
+        if self.find(&name).is_some() {
+            let same_name_sec_mut = self.get_mut(name.as_str()).unwrap().unwrap();
             for opt in section.options.into_iter() {
                 same_name_sec_mut.merge(opt)
             }
@@ -139,73 +406,193 @@ impl UciConfig {
         self.add(section)
     }
 
-    pub fn del(&mut self, name: &str) {
-        if let Some(idx) = self
-            .sections
-            .iter()
-            .position(|sec| self.get_section_name(sec) == name)
-        {
+    pub fn merge_config(&mut self, other: UciConfig) {
+        for sec in other.sections.into_iter() {
+            if sec.name.is_empty() {
+                self.add(sec);
+            } else {
+                self.merge(sec);
+            }
+        }
+    }
+
+    pub fn del(&mut self, name: &str) -> bool {
+        if let Ok(Some(idx)) = self.position(name) {
+            self.invalidate_index();
             self.sections.remove(idx);
+            return true;
         };
+        false
     }
 
     pub fn del_all(&mut self, typ: &str) {
-        let secs = self
-            .sections
-            .clone()
+        self.invalidate_index();
+        self.sections.retain(|sec| sec.sec_type != typ);
+    }
+
+    pub fn drain_all(&mut self, typ: &str) -> Vec<UciSection> {
+        let (drained, kept) = std::mem::take(&mut self.sections)
             .into_iter()
-            .filter(|sec| sec.sec_type != typ)
+            .partition(|sec| sec.sec_type == typ);
+        self.invalidate_index();
+        self.sections = kept;
+        drained
+    }
+
+    pub fn map_values<F: FnMut(&str, &str, &str) -> Option<String>>(&mut self, mut f: F) {
+        let names: Vec<String> = self
+            .sections
+            .iter()
+            .map(|sec| self.get_section_name(sec))
             .collect();
-        self.sections = secs;
+        for (sec, name) in self.sections.iter_mut().zip(names) {
+            for opt in sec.options.iter_mut() {
+                for value in opt.values.iter_mut() {
+                    match f(&name, &opt.name, value) {
+                        Some(new) if new != *value => {
+                            *value = new;
+                            self.modified = true;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn duplicate_options(&self) -> Vec<(String, String)> {
+        let mut duplicates = vec![];
+        for sec in self.sections.iter() {
+            for (i, opt) in sec.options.iter().enumerate() {
+                let seen = sec.options[..i]
+                    .iter()
+                    .filter(|o| o.name == opt.name)
+                    .count();
+                if seen == 1 {
+                    duplicates.push((self.get_section_name(sec), opt.name.clone()));
+                }
+            }
+        }
+        duplicates
+    }
+}
+
+impl fmt::Display for UciConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_source_string(&WriteOptions::default()))
+    }
+}
+
+fn _write_section<W: Write>(buf: &mut W, sec: &UciSection, opts: &WriteOptions) -> fmt::Result {
+    buf.write_char('\n')?;
+    for comment in sec.comments.iter() {
+        writeln!(buf, "{}", comment)?;
     }
+
+    if sec.name.is_empty() {
+        write!(buf, "config {}", sec.sec_type)?;
+    } else {
+        let name = quote_name(&sec.name, opts);
+        write!(buf, "config {} {}", sec.sec_type, name)?;
+    }
+    _write_line_end(buf, sec.inline_comment.as_deref())?;
+
+    for opt in sec.options.iter() {
+        for comment in opt.comments.iter() {
+            writeln!(buf, "{}{}", opts.indent, comment)?;
+        }
+        let values = match opt.opt_type {
+            UciOptionType::TypeOption => &opt.values[..opt.values.len().min(1)],
+            UciOptionType::TypeList => &opt.values[..],
+        };
+        for (i, v) in values.iter().enumerate() {
+            let keyword = opt.opt_type.as_keyword();
+            let value = quote_value(v, opt.quote_at(i), opts.quote);
+            write!(buf, "{}{} {} {}", opts.indent, keyword, opt.name, value)?;
+            _write_line_end(buf, opt.inline_comment_at(i))?;
+        }
+    }
+    Ok(())
+}
+
+fn _write_line_end<W: Write>(buf: &mut W, comment: Option<&str>) -> fmt::Result {
+    match comment {
+        Some(comment) => writeln!(buf, " {}", comment),
+        None => buf.write_char('\n'),
+    }
+}
+
+fn quote_name(name: &str, opts: &WriteOptions) -> String {
+    quote_value(name, UciQuoteStyle::Single, opts.quote)
+}
+
+fn quote_value(value: &str, style: UciQuoteStyle, quote: char) -> String {
+    let plain = !value.contains(['\'', '\\', '\n', '\r']);
+    if quote == '\'' && style == UciQuoteStyle::Single && plain {
+        return format!("'{}'", value);
+    }
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
 }
 
 fn unmangle_section_name(section_name: &str) -> Result<(String, i32)> {
-    let len = section_name.len();
-    let bytes_section_name = section_name.as_bytes();
-    if len < 5 {
-        return Err(Error::new(
+    if section_name.chars().count() < 5 {
+        return Err(Error::invalid_name(
             "implausible section selector: must be at least 5 characters long",
         ));
     };
 
-    if bytes_section_name[0] as char != '@' {
-        return Err(Error::new(
+    if !section_name.starts_with('@') {
+        return Err(Error::invalid_name(
             "invalid syntax: section selector must start with @ sign",
         ));
     };
 
-    let (mut bra, ket) = (0, len - 1);
+    let (mut bra, ket) = (0, section_name.len() - 1);
 
-    for (i, r) in bytes_section_name.iter().enumerate() {
-        if i != 0 && *r as char == '@' {
-            return Err(Error::new("invalid syntax: multiple @ signs found"));
+    for (i, r) in section_name.char_indices() {
+        if i != 0 && r == '@' {
+            return Err(Error::invalid_name(
+                "invalid syntax: multiple @ signs found",
+            ));
         };
-        if bra > 0 && *r as char == '[' {
-            return Err(Error::new("invalid syntax: multiple open brackets found"));
+        if bra > 0 && r == '[' {
+            return Err(Error::invalid_name(
+                "invalid syntax: multiple open brackets found",
+            ));
         };
-        if i != ket && *r as char == ']' {
-            return Err(Error::new("invalid syntax: multiple closed brackets found"));
+        if i != ket && r == ']' {
+            return Err(Error::invalid_name(
+                "invalid syntax: multiple closed brackets found",
+            ));
         };
-        if *r as char == '[' {
+        if r == '[' {
             bra = i;
         };
     }
 
-    if bra == 0 || bra >= ket {
-        return Err(Error::new(
+    if bra == 0 || bra >= ket || !section_name.ends_with(']') {
+        return Err(Error::invalid_name(
             "invalid syntax: section selector must have format '@type[index]'",
         ));
     };
 
-    let sec_type = from_utf8(&bytes_section_name[1..bra]).unwrap().to_string();
-    let sec_index = match from_utf8(&bytes_section_name[bra + 1..ket])
-        .unwrap()
-        .parse::<i32>()
-    {
+    let sec_type = section_name[1..bra].to_string();
+    let sec_index = match section_name[bra + 1..ket].parse::<i32>() {
         Ok(num) => num,
         Err(err) => {
-            return Err(Error::new(format!(
+            return Err(Error::invalid_name(format!(
                 "invalid syntax: index must be numeric: {}",
                 err
             )))
@@ -49,6 +49,20 @@ fn test_unmangle_section_name() {
             "@abcdEFGHijkl[0xff]",
             Err("invalid syntax: index must be numeric: invalid digit found in string".to_string()),
         ),
+        ("@café[0]", Ok(("café".to_string(), 0))),
+        ("@é[-1]", Ok(("é".to_string(), -1))),
+        (
+            "@é[]",
+            Err("implausible section selector: must be at least 5 characters long".to_string()),
+        ),
+        (
+            "@a[0é",
+            Err("invalid syntax: section selector must have format '@type[index]'".to_string()),
+        ),
+        (
+            "@a[0x",
+            Err("invalid syntax: section selector must have format '@type[index]'".to_string()),
+        ),
     ];
 
     for (name, expected) in test_cases {
@@ -73,6 +87,9 @@ fn test_config_get() {
         UciSection {
             name: "named".to_string(),
             sec_type: "foo".to_string(),
+            comments: vec![],
+            package: String::new(),
+            inline_comment: None,
             options: vec![
                 UciOption::new("pos", UciOptionType::TypeOption, vec![format!("3")]),
                 UciOption::new("unnamed", UciOptionType::TypeOption, vec![format!("0")]),
@@ -86,6 +103,9 @@ fn test_config_get() {
         UciSection {
             name: "@foo[0]".to_string(),
             sec_type: "foo".to_string(),
+            comments: vec![],
+            package: String::new(),
+            inline_comment: None,
             options: vec![
                 UciOption::new("pos", UciOptionType::TypeOption, vec![format!("3")]),
                 UciOption::new("unnamed", UciOptionType::TypeOption, vec![format!("0")]),
@@ -99,6 +119,9 @@ fn test_config_get() {
         UciSection {
             name: "@foo[1]".to_string(),
             sec_type: "foo".to_string(),
+            comments: vec![],
+            package: String::new(),
+            inline_comment: None,
             options: vec![
                 UciOption::new("pos", UciOptionType::TypeOption, vec![format!("1")]),
                 UciOption::new("unnamed", UciOptionType::TypeOption, vec![format!("1")]),
@@ -108,6 +131,9 @@ fn test_config_get() {
         UciSection {
             name: "@foo[2]".to_string(),
             sec_type: "foo".to_string(),
+            comments: vec![],
+            package: String::new(),
+            inline_comment: None,
             options: vec![
                 UciOption::new("pos", UciOptionType::TypeOption, vec![format!("2")]),
                 UciOption::new("unnamed", UciOptionType::TypeOption, vec![format!("1")]),
@@ -117,6 +143,9 @@ fn test_config_get() {
         UciSection {
             name: "@foo[-3]".to_string(),
             sec_type: "foo".to_string(),
+            comments: vec![],
+            package: String::new(),
+            inline_comment: None,
             options: vec![
                 UciOption::new("pos", UciOptionType::TypeOption, vec![format!("3")]),
                 UciOption::new("unnamed", UciOptionType::TypeOption, vec![format!("0")]),
@@ -130,6 +159,9 @@ fn test_config_get() {
         UciSection {
             name: "@foo[-2]".to_string(),
             sec_type: "foo".to_string(),
+            comments: vec![],
+            package: String::new(),
+            inline_comment: None,
             options: vec![
                 UciOption::new("pos", UciOptionType::TypeOption, vec![format!("1")]),
                 UciOption::new("unnamed", UciOptionType::TypeOption, vec![format!("1")]),
@@ -139,6 +171,9 @@ fn test_config_get() {
         UciSection {
             name: "@foo[-1]".to_string(),
             sec_type: "foo".to_string(),
+            comments: vec![],
+            package: String::new(),
+            inline_comment: None,
             options: vec![
                 UciOption::new("pos", UciOptionType::TypeOption, vec![format!("2")]),
                 UciOption::new("unnamed", UciOptionType::TypeOption, vec![format!("1")]),
@@ -168,12 +203,20 @@ fn test_config_del() {
         (
             UciConfig {
                 name: "test_config".to_owned(),
+                pkg_name: String::new(),
                 sections: vec![UciSection {
                     name: "named".to_string(),
                     sec_type: "foo".to_string(),
+                    comments: vec![],
+                    package: String::new(),
+                    inline_comment: None,
                     options: vec![],
                 }],
                 modified: false,
+                trailing_comments: vec![],
+                header_comments: vec![],
+                source_edges: None,
+                type_index: Default::default(),
             },
             "named",
             None,
@@ -181,12 +224,20 @@ fn test_config_del() {
         (
             UciConfig {
                 name: "test_config".to_owned(),
+                pkg_name: String::new(),
                 sections: vec![UciSection {
                     name: "".to_string(),
                     sec_type: "foo".to_string(),
+                    comments: vec![],
+                    package: String::new(),
+                    inline_comment: None,
                     options: vec![],
                 }],
                 modified: false,
+                trailing_comments: vec![],
+                header_comments: vec![],
+                source_edges: None,
+                type_index: Default::default(),
             },
             "@foo[0]",
             None,
@@ -194,9 +245,326 @@ fn test_config_del() {
     ];
 
     for (mut cfg, del_name, expected) in test_cases {
-        cfg.del(del_name);
+        assert!(cfg.del(del_name));
+        assert!(!cfg.del(del_name));
         if let Ok(sec) = cfg.get(del_name) {
             assert_eq!(sec, expected);
         };
     }
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_config_serde_round_trip() {
+    let config = uci_parse(
+        "uci_config",
+        include_str!("../../../tests/.test_data/uci_config").to_string(),
+    )
+    .unwrap();
+
+    let json = serde_json::to_string(&config).unwrap();
+    assert!(json.contains(r#""opt_type":"list""#));
+    assert!(json.contains(r#""opt_type":"option""#));
+
+    let decoded: UciConfig = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.name, config.name);
+    assert_eq!(decoded.pkg_name, config.pkg_name);
+    assert_eq!(decoded.sections, config.sections);
+    assert_eq!(decoded.trailing_comments, config.trailing_comments);
+}
+
+#[test]
+fn test_config_duplicate_options() {
+    let mut config = UciConfig::new("test");
+    let mut named = UciSection::new("interface", "lan");
+    for (name, value) in [
+        ("proto", "static"),
+        ("proto", "dhcp"),
+        ("proto", "none"),
+        ("ipaddr", "10.0.0.1"),
+    ] {
+        let opt = UciOption::new(name, UciOptionType::TypeOption, vec![value.to_string()]);
+        named.add(opt);
+    }
+    config.add(named);
+
+    let mut unnamed = UciSection::new("rule", "");
+    for typ in [UciOptionType::TypeOption, UciOptionType::TypeList] {
+        unnamed.add(UciOption::new("dest", typ, vec![format!("wan")]));
+    }
+    config.add(unnamed);
+
+    assert_eq!(
+        config.duplicate_options(),
+        vec![
+            ("lan".to_string(), "proto".to_string()),
+            ("@rule[0]".to_string(), "dest".to_string()),
+        ]
+    );
+
+    config.get_mut("lan").unwrap().unwrap().del("proto");
+    config.get_mut("lan").unwrap().unwrap().del("proto");
+    config.get_mut("@rule[0]").unwrap().unwrap().del("dest");
+    assert!(config.duplicate_options().is_empty());
+}
+
+#[test]
+fn test_config_del_all() {
+    let mut config = UciConfig::new("test_config");
+    for (typ, name) in [("foo", ""), ("bar", "keep"), ("foo", "named"), ("foo", "")] {
+        config.add(UciSection::new(typ, name));
+    }
+    config.del_all("foo");
+    assert_eq!(config.sections, vec![UciSection::new("bar", "keep")]);
+    assert!(config.get("@foo[0]").is_err());
+
+    config.del_all("missing");
+    assert_eq!(config.sections.len(), 1);
+}
+
+#[test]
+fn test_config_get_by_index() {
+    let mut config = UciConfig::new("test_config");
+    for (typ, name) in [("foo", ""), ("bar", ""), ("foo", "named"), ("foo", "")] {
+        config.add(UciSection::new(typ, name));
+    }
+
+    for (index, mangled) in [
+        (0, "@foo[0]"),
+        (2, "@foo[2]"),
+        (-1, "@foo[-1]"),
+        (-3, "@foo[-3]"),
+    ] {
+        assert_eq!(
+            config.get_by_index("foo", index).unwrap(),
+            config.get(mangled).unwrap()
+        );
+    }
+    let named = config.get_by_index("foo", 1).unwrap().unwrap();
+    assert_eq!(named.name, "named");
+    let bar = config.get_by_index("bar", -1).unwrap().unwrap();
+    assert_eq!(bar.sec_type, "bar");
+    assert!(config.get_by_index("foo", 3).is_err());
+    assert!(config.get_by_index("foo", -4).is_err());
+    assert!(config.get_by_index("baz", 0).is_err());
+}
+
+#[test]
+fn test_config_merge_config() {
+    let mut base = uci_parse(
+        "base",
+        "config interface 'lan'\n\toption proto 'static'\nconfig rule\n\toption name 'a'\n"
+            .to_string(),
+    )
+    .unwrap();
+    let other = uci_parse(
+        "override",
+        "config interface 'lan'\n\toption ipaddr '10.0.0.1'\nconfig rule\n\toption name 'b'\n"
+            .to_string(),
+    )
+    .unwrap();
+    base.merge_config(other);
+
+    let lan = base.get("lan").unwrap().unwrap();
+    assert_eq!(lan.get("proto").unwrap().values, vec![format!("static")]);
+    assert_eq!(lan.get("ipaddr").unwrap().values, vec![format!("10.0.0.1")]);
+    assert_eq!(base.sections.len(), 3);
+    let rule = base.get("@rule[1]").unwrap().unwrap();
+    assert_eq!(rule.get("name").unwrap().values, vec![format!("b")]);
+}
+
+#[test]
+fn test_config_len() {
+    let mut config = UciConfig::new("test_config");
+    assert!(config.is_empty());
+    assert_eq!(config.len(), 0);
+
+    config.add(UciSection::new("foo", ""));
+    config.add(UciSection::new("bar", "named"));
+    assert!(!config.is_empty());
+    assert_eq!(config.len(), 2);
+
+    config.del_all("foo");
+    assert_eq!(config.len(), 1);
+}
+
+#[test]
+fn test_config_get_or_insert_section() {
+    let mut config = UciConfig::new("network");
+    let lan = config.get_or_insert_section("interface", "lan");
+    lan.add(UciOption::new(
+        "proto",
+        UciOptionType::TypeOption,
+        vec![format!("static")],
+    ));
+    assert_eq!(config.len(), 1);
+
+    let lan = config.get_or_insert_section("interface", "lan");
+    lan.add(UciOption::new(
+        "mtu",
+        UciOptionType::TypeOption,
+        vec![format!("1400")],
+    ));
+    assert_eq!(config.len(), 1);
+    let lan = config.get("lan").unwrap().unwrap();
+    assert_eq!(lan.get("proto").unwrap().values, vec![format!("static")]);
+    assert_eq!(lan.get("mtu").unwrap().values, vec![format!("1400")]);
+
+    config.get_or_insert_section("rule", "");
+    config.get_or_insert_section("rule", "");
+    assert_eq!(config.len(), 3);
+    assert!(config.get("@rule[1]").unwrap().is_some());
+
+    let lan = config.get_or_insert_section("device", "lan");
+    assert_eq!(lan.sec_type, "device");
+    assert!(lan.options.is_empty());
+    assert_eq!(config.len(), 3);
+    assert_eq!(config.get_section_name(&config.sections[2]), "lan");
+}
+
+#[test]
+fn test_config_has_content() {
+    for input in ["", "\n\n", "# notes\n# more\n", "\n# notes\n\n"] {
+        let config = uci_parse("empty", input.to_string()).unwrap();
+        assert!(!config.has_content());
+        assert!(config.is_empty());
+    }
+    let config = uci_parse("pkg", "package 'net'\n".to_string()).unwrap();
+    assert!(config.has_content());
+    assert!(config.is_empty());
+    let config = uci_parse("sec", "config rule\n".to_string()).unwrap();
+    assert!(config.has_content());
+}
+
+#[test]
+fn test_config_map_values() {
+    let mut config = uci_parse(
+        "network",
+        "config interface 'lan'\n\toption ifname 'eth0'\n\tlist ports 'eth0'\n\tlist ports 'eth1'\nconfig rule\n\toption src 'eth0'\n"
+            .to_string(),
+    )
+    .unwrap();
+
+    let mut seen = vec![];
+    config.map_values(|section, option, value| {
+        seen.push(format!("{}.{}={}", section, option, value));
+        None
+    });
+    assert_eq!(
+        seen,
+        vec![
+            "lan.ifname=eth0",
+            "lan.ports=eth0",
+            "lan.ports=eth1",
+            "@rule[0].src=eth0"
+        ]
+    );
+    assert!(!config.modified);
+
+    config.map_values(|_, _, value| (value == "eth0").then(|| "eth0".to_string()));
+    assert!(!config.modified);
+
+    config.map_values(|section, _, value| {
+        (section != "@rule[0]" && value == "eth0").then(|| "br-lan".to_string())
+    });
+    assert!(config.modified);
+    let lan = config.get("lan").unwrap().unwrap();
+    assert_eq!(lan.get("ifname").unwrap().values, vec![format!("br-lan")]);
+    let ports = &lan.get("ports").unwrap().values;
+    assert_eq!(ports, &vec![format!("br-lan"), format!("eth1")]);
+    let rule = config.get("@rule[0]").unwrap().unwrap();
+    assert_eq!(rule.get("src").unwrap().values, vec![format!("eth0")]);
+}
+
+#[test]
+fn test_config_get_non_ascii_type() {
+    let mut config = UciConfig::new("test_config");
+    config.add(UciSection::new("café", ""));
+    config.add(UciSection::new("café", "named"));
+
+    let name = config.get_section_name(&config.sections[0]);
+    assert_eq!(name, "@café[0]");
+    let sec = config.get("@café[-1]").unwrap().unwrap();
+    assert_eq!(sec.name, "named");
+    assert!(config.get("@café[2]").is_err());
+}
+
+#[test]
+fn test_config_sections_by_type() {
+    let config = uci_parse(
+        "uci_config",
+        include_str!("../../../tests/.test_data/uci_config").to_string(),
+    )
+    .unwrap();
+
+    let internal = config.sections_by_type("internal");
+    assert_eq!(internal.len(), 4);
+    let names: Vec<&str> = internal.iter().map(|sec| sec.name.as_str()).collect();
+    assert_eq!(names, vec!["languages", "sauth", "ccache", "themes"]);
+    let sauth = internal[1].get("sessiontime").unwrap();
+    assert_eq!(sauth.values, vec![format!("3600")]);
+    assert!(config.sections_by_type("missing").is_empty());
+}
+
+#[test]
+fn test_config_type_index() {
+    let mut config = UciConfig::new("firewall");
+    for i in 0..20000 {
+        let typ = if i % 4 == 0 { "redirect" } else { "rule" };
+        config.add(UciSection::new(typ, ""));
+    }
+    config.add(UciSection::new("defaults", "main"));
+
+    for sec in config.sections.iter() {
+        let name = config.get_section_name(sec);
+        assert!(std::ptr::eq(config.get(&name).unwrap().unwrap(), sec));
+    }
+    assert_eq!(config.get_section_name(&config.sections[7]), "@rule[5]");
+    assert_eq!(config.get_section_name(&config.sections[20000]), "main");
+    assert!(config.get("@rule[15000]").is_err());
+
+    config.del("@rule[0]");
+    let first = config.get("@rule[0]").unwrap().unwrap();
+    assert!(std::ptr::eq(first, &config.sections[1]));
+    config.prepend(UciSection::new("rule", "early"));
+    assert_eq!(config.get("@rule[0]").unwrap().unwrap().name, "early");
+    config.del_all("redirect");
+    assert_eq!(config.get_section_name(&config.sections[1]), "@rule[1]");
+    assert!(config.get("@redirect[0]").is_err());
+    config.add(UciSection::new("redirect", "late"));
+    assert_eq!(config.get("@redirect[0]").unwrap().unwrap().name, "late");
+}
+
+#[test]
+fn test_config_lookup_after_direct_mutation() {
+    let mut config = UciConfig::new("test_config");
+    config.add(UciSection::new("a", ""));
+    config.add(UciSection::new("b", ""));
+    assert_eq!(config.get_section_name(&config.sections[1]), "@b[0]");
+
+    config.sections.swap(0, 1);
+    let first = config.get("@b[0]").unwrap().unwrap();
+    assert!(std::ptr::eq(first, &config.sections[0]));
+    assert_eq!(config.get_section_name(&config.sections[1]), "@a[0]");
+
+    config.get_mut("@a[0]").unwrap().unwrap().sec_type = "b".to_string();
+    assert_eq!(config.get_section_name(&config.sections[1]), "@b[1]");
+    assert!(config.get("@a[0]").is_err());
+}
+
+#[test]
+fn test_config_is_sync() {
+    fn assert_sync<T: Sync>() {}
+    assert_sync::<UciConfig>();
+}
+
+#[test]
+fn test_config_get_section_name_identical_sections() {
+    let mut config = UciConfig::new("test_config");
+    config.add(UciSection::new("foo", ""));
+    config.add(UciSection::new("foo", ""));
+
+    let copy = UciSection::new("foo", "");
+    assert_eq!(config.get_section_name(&config.sections[1]), "@foo[1]");
+    assert_eq!(config.get_section_name(&copy), "@foo[0]");
+}
@@ -2,6 +2,6 @@ mod imp;
 mod uci_option;
 mod uci_section;
 
-pub use self::imp::UciConfig;
-pub use self::uci_option::{UciOption, UciOptionType};
+pub use self::imp::{UciConfig, WriteOptions};
+pub use self::uci_option::{UciOption, UciOptionType, UciQuoteStyle};
 pub use self::uci_section::UciSection;
@@ -1,35 +1,126 @@
 use std::collections::HashSet;
 
 #[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UciOption {
     pub name: String,
     pub values: Vec<String>,
     pub opt_type: UciOptionType,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub comments: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quotes: Vec<UciQuoteStyle>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inline_comments: Vec<Option<String>>,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UciOptionType {
+    #[cfg_attr(feature = "serde", serde(rename = "option"))]
     TypeOption,
+    #[cfg_attr(feature = "serde", serde(rename = "list"))]
     TypeList,
 }
 
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UciQuoteStyle {
+    #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "single"))]
+    Single,
+    #[cfg_attr(feature = "serde", serde(rename = "double"))]
+    Double,
+}
+
+impl UciOptionType {
+    pub fn as_keyword(&self) -> &'static str {
+        match self {
+            UciOptionType::TypeOption => "option",
+            UciOptionType::TypeList => "list",
+        }
+    }
+
+    pub fn from_keyword(keyword: &str) -> Option<UciOptionType> {
+        match keyword {
+            "option" => Some(UciOptionType::TypeOption),
+            "list" => Some(UciOptionType::TypeList),
+            _ => None,
+        }
+    }
+}
+
 impl UciOption {
     pub fn new(name: &str, opt_type: UciOptionType, values: Vec<String>) -> UciOption {
         UciOption {
             name: name.into(),
             opt_type,
             values,
+            comments: Vec::new(),
+            quotes: Vec::new(),
+            inline_comments: Vec::new(),
         }
     }
 
     pub fn set_values(&mut self, values: Vec<String>) {
         self.values = values;
+        self.quotes.clear();
+        self.inline_comments.clear();
+    }
+
+    pub fn quote_at(&self, index: usize) -> UciQuoteStyle {
+        self.quotes.get(index).copied().unwrap_or_default()
+    }
+
+    pub fn set_quote(&mut self, index: usize, style: UciQuoteStyle) {
+        if index >= self.quotes.len() {
+            if style == UciQuoteStyle::default() {
+                return;
+            }
+            self.quotes.resize(index + 1, UciQuoteStyle::default());
+        }
+        self.quotes[index] = style;
+    }
+
+    pub fn inline_comment_at(&self, index: usize) -> Option<&str> {
+        self.inline_comments.get(index)?.as_deref()
+    }
+
+    pub fn set_inline_comment(&mut self, index: usize, comment: &str) {
+        if index >= self.inline_comments.len() {
+            self.inline_comments.resize(index + 1, None);
+        }
+        self.inline_comments[index] = Some(comment.to_string());
+    }
+
+    pub fn remove_value(&mut self, index: usize) -> String {
+        if index < self.quotes.len() {
+            self.quotes.remove(index);
+        }
+        if index < self.inline_comments.len() {
+            self.inline_comments.remove(index);
+        }
+        self.values.remove(index)
     }
 
     pub fn set_type(&mut self, typ: UciOptionType) {
         self.opt_type = typ;
     }
 
+    pub fn push_value(&mut self, value: &str) {
+        self.values.push(value.to_string());
+    }
+
+    pub fn values_eq_unordered(&self, other: &UciOption) -> bool {
+        if self.opt_type != other.opt_type || self.values.len() != other.values.len() {
+            return false;
+        }
+        let mut values: Vec<&String> = self.values.iter().collect();
+        let mut other_values: Vec<&String> = other.values.iter().collect();
+        values.sort();
+        other_values.sort();
+        values == other_values
+    }
 
     pub fn merge_values(&mut self, values: Vec<String>) {
         match self.opt_type {
@@ -30,3 +30,61 @@ fn test_option_merge_values() {
         assert_eq!(opt.values, expected);
     }
 }
+
+#[test]
+fn test_option_values_eq_unordered() {
+    let test_cases = vec![
+        (
+            UciOption::new("pos", UciOptionType::TypeList, vec![format!("3"), format!("5")]),
+            UciOption::new("pos", UciOptionType::TypeList, vec![format!("5"), format!("3")]),
+            true,
+        ),
+        (
+            UciOption::new("pos", UciOptionType::TypeList, vec![format!("3"), format!("3")]),
+            UciOption::new("pos", UciOptionType::TypeList, vec![format!("3"), format!("5")]),
+            false,
+        ),
+        (
+            UciOption::new("pos", UciOptionType::TypeList, vec![format!("3")]),
+            UciOption::new("pos", UciOptionType::TypeOption, vec![format!("3")]),
+            false,
+        ),
+    ];
+
+    for (opt, other, expected) in test_cases {
+        assert_eq!(opt.values_eq_unordered(&other), expected);
+    }
+}
+
+#[test]
+fn test_option_type_keyword() {
+    for typ in [UciOptionType::TypeOption, UciOptionType::TypeList] {
+        assert_eq!(UciOptionType::from_keyword(typ.as_keyword()), Some(typ));
+    }
+    assert_eq!(UciOptionType::TypeOption.as_keyword(), "option");
+    assert_eq!(UciOptionType::TypeList.as_keyword(), "list");
+    assert_eq!(UciOptionType::from_keyword("config"), None);
+    assert_eq!(UciOptionType::from_keyword("List"), None);
+    assert_eq!(UciOptionType::from_keyword(""), None);
+}
+
+#[test]
+fn test_option_quote_style() {
+    let values = vec![format!("a"), format!("b"), format!("c")];
+    let mut opt = UciOption::new("list", UciOptionType::TypeList, values);
+    opt.set_quote(0, UciQuoteStyle::Single);
+    assert!(opt.quotes.is_empty());
+
+    opt.set_quote(1, UciQuoteStyle::Double);
+    assert_eq!(opt.quote_at(0), UciQuoteStyle::Single);
+    assert_eq!(opt.quote_at(1), UciQuoteStyle::Double);
+    assert_eq!(opt.quote_at(2), UciQuoteStyle::Single);
+
+    assert_eq!(opt.remove_value(0), "a");
+    assert_eq!(opt.quote_at(0), UciQuoteStyle::Double);
+    assert_eq!(opt.remove_value(1), "c");
+    assert_eq!(opt.values, vec![format!("b")]);
+
+    opt.set_values(vec![format!("d")]);
+    assert_eq!(opt.quote_at(0), UciQuoteStyle::Single);
+}
@@ -1,10 +1,17 @@
 use super::uci_option::UciOption;
 
 #[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UciSection {
     pub name: String,
     pub sec_type: String,
     pub options: Vec<UciOption>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub comments: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub package: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inline_comment: Option<String>,
 }
 
 impl UciSection {
@@ -13,6 +20,9 @@ impl UciSection {
             name: name.into(),
             sec_type: sec_type.into(),
             options: Vec::new(),
+            comments: Vec::new(),
+            package: String::new(),
+            inline_comment: None,
         }
     }
 
@@ -57,9 +67,29 @@ impl UciSection {
         self.options.iter().find(|opt| opt.name == name)
     }
 
+    pub fn get_all(&self, name: &str) -> Vec<&UciOption> {
+        self.options.iter().filter(|opt| opt.name == name).collect()
+    }
+
+    pub fn iter_options(&self) -> impl Iterator<Item = &UciOption> {
+        self.options.iter()
+    }
+
+    pub fn iter_options_mut(&mut self) -> impl Iterator<Item = &mut UciOption> {
+        self.options.iter_mut()
+    }
+
     pub fn get_mut(&mut self, name: &str) -> Option<&mut UciOption> {
         self.options.iter_mut().find(|opt| opt.name == name)
     }
+
+    pub fn options_eq_unordered(&self, other: &UciSection) -> bool {
+        self.options.len() == other.options.len()
+            && self.options.iter().all(|opt| match other.get(&opt.name) {
+                Some(other_opt) => opt.values_eq_unordered(other_opt),
+                None => false,
+            })
+    }
 }
 
 #[cfg(test)]
@@ -9,6 +9,9 @@ fn test_section_merge() {
             UciSection {
                 name: "named".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeOption,
@@ -19,6 +22,9 @@ fn test_section_merge() {
             UciSection {
                 name: "named".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeOption,
@@ -30,6 +36,9 @@ fn test_section_merge() {
             UciSection {
                 name: "named".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeOption,
@@ -44,6 +53,9 @@ fn test_section_merge() {
             UciSection {
                 name: "named".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeList,
@@ -55,6 +67,9 @@ fn test_section_merge() {
             UciSection {
                 name: "named".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeList,
@@ -65,6 +80,9 @@ fn test_section_merge() {
             UciSection {
                 name: "named".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeOption,
@@ -76,6 +94,9 @@ fn test_section_merge() {
             UciSection {
                 name: "named".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeList,
@@ -86,6 +107,9 @@ fn test_section_merge() {
             UciSection {
                 name: "named".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeList,
@@ -107,6 +131,9 @@ fn test_section_del() {
             UciSection {
                 name: "@foo[-1]".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "pos",
                     UciOptionType::TypeOption,
@@ -120,6 +147,9 @@ fn test_section_del() {
             UciSection {
                 name: "@foo[-1]".to_string(),
                 sec_type: "foo".to_string(),
+                comments: vec![],
+                package: String::new(),
+                inline_comment: None,
                 options: vec![UciOption::new(
                     "list",
                     UciOptionType::TypeList,
@@ -136,3 +166,66 @@ fn test_section_del() {
         assert_eq!(sec.get(del_name), expected);
     }
 }
+
+#[test]
+fn test_section_options_eq_unordered() {
+    let mut sec = UciSection::new("foo", "named");
+    sec.add(UciOption::new("pos", UciOptionType::TypeOption, vec![format!("3")]));
+    sec.add(UciOption::new(
+        "list",
+        UciOptionType::TypeList,
+        vec![format!("a"), format!("b"), format!("a")],
+    ));
+
+    let mut other = UciSection::new("foo", "named");
+    other.add(UciOption::new(
+        "list",
+        UciOptionType::TypeList,
+        vec![format!("b"), format!("a"), format!("a")],
+    ));
+    other.add(UciOption::new("pos", UciOptionType::TypeOption, vec![format!("3")]));
+    assert!(sec.options_eq_unordered(&other));
+
+    other.get_mut("list").unwrap().set_values(vec![format!("a"), format!("b")]);
+    assert!(!sec.options_eq_unordered(&other));
+
+    other.del("list");
+    assert!(!sec.options_eq_unordered(&other));
+}
+
+#[test]
+fn test_section_get_all() {
+    let mut sec = UciSection::new("foo", "named");
+    assert!(sec.get_all("pos").is_empty());
+
+    let first = UciOption::new("pos", UciOptionType::TypeOption, vec![format!("1")]);
+    let second = UciOption::new("pos", UciOptionType::TypeList, vec![format!("2")]);
+    let other = UciOption::new("other", UciOptionType::TypeOption, vec![format!("3")]);
+    sec.add(first.clone());
+    sec.add(other);
+    sec.add(second.clone());
+
+    assert_eq!(sec.get_all("pos"), vec![&first, &second]);
+    assert_eq!(sec.get("pos"), Some(&first));
+    assert!(sec.get_all("missing").is_empty());
+}
+
+#[test]
+fn test_section_iter_options() {
+    let mut sec = UciSection::new("foo", "named");
+    assert_eq!(sec.iter_options().count(), 0);
+
+    let pos = UciOption::new("pos", UciOptionType::TypeOption, vec![format!("1")]);
+    let list = UciOption::new("list", UciOptionType::TypeList, vec![format!("a")]);
+    sec.add(pos);
+    sec.add(list);
+    assert_eq!(sec.iter_options().count(), 2);
+    let names: Vec<&str> = sec.iter_options().map(|opt| opt.name.as_str()).collect();
+    assert_eq!(names, vec!["pos", "list"]);
+
+    for opt in sec.iter_options_mut() {
+        opt.set_values(vec![format!("x")]);
+    }
+    let values: Vec<&Vec<String>> = sec.iter_options().map(|opt| &opt.values).collect();
+    assert_eq!(values, vec![&vec![format!("x")], &vec![format!("x")]]);
+}
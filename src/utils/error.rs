@@ -6,27 +6,99 @@ use std::{error, fmt, io};
 
 use crate::file::TempFile;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    Io,
+    Parse,
+    NotFound,
+    InvalidName,
+    AlreadyExists,
+    Other,
+}
+
 #[derive(Debug)]
 pub struct Error {
     pub message: String,
+    pub line: Option<usize>,
+    pub col: Option<usize>,
+    pub pos: Option<usize>,
+    pub span: Option<(usize, usize)>,
+    kind: ErrorKind,
 }
 
 impl Error {
     pub fn new<T>(message: T) -> Error
+    where
+        T: Into<String>,
+    {
+        Self::of_kind(ErrorKind::Other, message)
+    }
+
+    pub fn io<T>(message: T) -> Error
+    where
+        T: Into<String>,
+    {
+        Self::of_kind(ErrorKind::Io, message)
+    }
+
+    pub fn parse<T>(message: T) -> Error
+    where
+        T: Into<String>,
+    {
+        Self::of_kind(ErrorKind::Parse, message)
+    }
+
+    pub fn not_found<T>(message: T) -> Error
+    where
+        T: Into<String>,
+    {
+        Self::of_kind(ErrorKind::NotFound, message)
+    }
+
+    pub fn invalid_name<T>(message: T) -> Error
+    where
+        T: Into<String>,
+    {
+        Self::of_kind(ErrorKind::InvalidName, message)
+    }
+
+    pub fn already_exists<T>(message: T) -> Error
+    where
+        T: Into<String>,
+    {
+        Self::of_kind(ErrorKind::AlreadyExists, message)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    fn of_kind<T>(kind: ErrorKind, message: T) -> Error
     where
         T: Into<String>,
     {
         Error {
             message: message.into(),
+            line: None,
+            col: None,
+            pos: None,
+            span: None,
+            kind,
         }
     }
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for Error {}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
-        Self {
-            message: err.to_string(),
-        }
+        Self::io(err.to_string())
     }
 }
 
@@ -56,25 +128,21 @@ impl error::Error for PathError {
 
 impl<F> From<PersistError<F>> for Error {
     fn from(err: PersistError<F>) -> Self {
-        Self {
-            message: format!(
-                "failed to persist temporary file, err: {:?}, file_name: {:?}",
-                err.error.to_string(),
-                err.file.path.as_os_str()
-            ),
-        }
+        Self::io(format!(
+            "failed to persist temporary file, err: {:?}, file_name: {:?}",
+            err.error.to_string(),
+            err.file.path.as_os_str()
+        ))
     }
 }
 
 impl From<PathError> for Error {
     fn from(err: PathError) -> Self {
-        Self {
-            message: format!(
-                "err: {:?}, path: {:?}",
-                err.error.to_string(),
-                err.path.to_str()
-            ),
-        }
+        Self::io(format!(
+            "err: {:?}, path: {:?}",
+            err.error.to_string(),
+            err.path.to_str()
+        ))
     }
 }
 
@@ -86,8 +154,6 @@ impl From<PathError> for io::Error {
 
 impl<W> From<IntoInnerError<W>> for Error {
     fn from(err: IntoInnerError<W>) -> Self {
-        Self {
-            message: err.error().to_string(),
-        }
+        Self::io(err.error().to_string())
     }
 }
@@ -1,6 +1,20 @@
 mod error;
 
 pub use error::Error;
+pub use error::ErrorKind;
 pub use error::PathError;
 pub use error::PersistError;
 pub type Result<T> = std::result::Result<T, Error>;
+
+pub fn compact_output(s: &str) -> String {
+    let mut output = String::with_capacity(s.len());
+    let mut prev_blank = false;
+    for line in s.split_inclusive('\n') {
+        let blank = line.trim().is_empty();
+        if !(blank && prev_blank) {
+            output.push_str(line);
+        }
+        prev_blank = blank;
+    }
+    output
+}
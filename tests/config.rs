@@ -1,7 +1,11 @@
 use std::fs::File;
 use std::io::Read;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 
-use uci_rs::{load_config, parse_raw_to_uci, save_config, Result, UciCommand};
+use uci_rs::{
+    commit_config, load_config, load_config_from, load_dir, parse_raw_to_uci, save_config,
+    save_config_with, ErrorKind, Result, SaveOptions, UciCommand,
+};
 
 #[test]
 fn test_uci_file_load_config() -> Result<()> {
@@ -10,6 +14,63 @@ fn test_uci_file_load_config() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_uci_file_load_config_from() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = load_config_from("uci_config", uci_str.as_bytes())?;
+    assert_eq!(uci.get_package(), "uci_config");
+    assert_eq!(
+        uci.to_string(),
+        load_config("uci_config", "tests/.test_data")?.to_string()
+    );
+
+    assert!(load_config_from("invalid", "config 'broken\n".as_bytes()).is_err());
+    let err = load_config_from("invalid", &[0xff, 0xfe][..])
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::Io);
+    assert!(err.message.starts_with("config: invalid: "));
+    assert!(load_config_from("invalid", &b"config 'a'\n\toption b '\xc3"[..]).is_err());
+
+    let err = load_config("missing", "tests/.test_data").err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Io);
+    assert!(err
+        .message
+        .starts_with("config: tests/.test_data/missing: "));
+    Ok(())
+}
+
+#[test]
+fn test_uci_file_load_dir() -> Result<()> {
+    let (configs, errors) = load_dir("tests/.test_data")?;
+    assert!(errors.is_empty());
+    let mut names: Vec<&String> = configs.keys().collect();
+    names.sort();
+    assert_eq!(names, vec!["uci_config", "uci_config_comments"]);
+    assert_eq!(configs["uci_config"].get_package(), "uci_config");
+
+    std::fs::create_dir_all(".tmp/load_dir/nested")?;
+    std::fs::write(".tmp/load_dir/network", "config interface 'lan'\n")?;
+    std::fs::write(".tmp/load_dir/broken", "config 'broken\n")?;
+    std::fs::write(".tmp/load_dir/network_a1B2c3.tmp", "config 'partial")?;
+    for (link, target) in [("linked", "network"), ("dangling", "missing")] {
+        let link = format!(".tmp/load_dir/{}", link);
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(target, link)?;
+    }
+    let (configs, errors) = load_dir(".tmp/load_dir")?;
+    assert_eq!(configs.len(), 2);
+    assert!(configs["network"].get_section("lan").is_ok());
+    assert!(configs["linked"].get_section("lan").is_ok());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind(), ErrorKind::Parse);
+    assert!(errors[0].message.contains("config: broken:1:"));
+
+    let err = load_dir(".tmp/missing_dir").err().unwrap();
+    assert!(err.message.starts_with("config: .tmp/missing_dir: "));
+    Ok(())
+}
+
 #[test]
 fn test_uci_file_save_config() -> Result<()> {
     let uci_str = include_str!(".test_data/uci_config");
@@ -21,3 +82,142 @@ fn test_uci_file_save_config() -> Result<()> {
     assert_eq!(contents.trim_end(), uci_str.trim_end());
     Ok(())
 }
+
+#[test]
+fn test_uci_file_save_config_with() -> Result<()> {
+    let uci_str = "\npackage 'secrets'\n\nconfig login 'admin'\n\toption password 'hunter2'\n\n";
+    let opts = SaveOptions {
+        mode: 0o600,
+        ..SaveOptions::default()
+    };
+    let uci = parse_raw_to_uci("secrets", uci_str.to_string())?;
+    save_config_with(".tmp", uci, opts)?;
+    let meta = std::fs::metadata(".tmp/secrets")?;
+    assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+    assert_eq!(std::fs::read_to_string(".tmp/secrets")?, uci_str);
+
+    let opts = SaveOptions {
+        uid: Some(meta.uid()),
+        gid: Some(meta.gid()),
+        ..opts
+    };
+    let uci = parse_raw_to_uci("secrets", uci_str.to_string())?;
+    save_config_with(".tmp", uci, opts)?;
+
+    if meta.uid() != 0 {
+        let opts = SaveOptions {
+            uid: Some(0),
+            ..opts
+        };
+        let uci = parse_raw_to_uci("secrets", uci_str.to_string())?;
+        let err = save_config_with(".tmp", uci, opts).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert!(err.message.starts_with("failed to change owner"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_uci_file_save_config_with_comments() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config_comments");
+    let uci = load_config("uci_config_comments", "tests/.test_data")?;
+    save_config(".tmp", uci)?;
+    let mut file = File::open(".tmp/uci_config_comments")?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, uci_str);
+    Ok(())
+}
+
+#[test]
+fn test_uci_file_save_config_without_package() -> Result<()> {
+    let uci_str = "\nconfig interface 'lan'\n\toption proto 'static'\n\n";
+    let uci = parse_raw_to_uci("no_package", uci_str.to_string())?;
+    assert_eq!(uci.get_package(), "no_package");
+    assert_eq!(uci.package_or_name(), "no_package");
+    save_config(".tmp", uci)?;
+    let mut file = File::open(".tmp/no_package")?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, uci_str);
+    Ok(())
+}
+
+#[test]
+fn test_uci_file_save_config_overwrite() -> Result<()> {
+    let uci_str = "\npackage 'overwrite'\n\nconfig interface 'lan'\n\toption proto 'static'\n\n";
+    let uci = parse_raw_to_uci("overwrite", uci_str.to_string())?;
+    save_config(".tmp", uci)?;
+
+    let mut uci = load_config("overwrite", ".tmp")?;
+    uci.set_option("lan", "proto", vec!["dhcp"])?;
+    save_config(".tmp", uci)?;
+
+    let uci = load_config("overwrite", ".tmp")?;
+    assert_eq!(uci.get_option("lan", "proto")?.1, &vec!["dhcp".to_string()]);
+    let leftovers = std::fs::read_dir(".tmp")?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("overwrite_"))
+        .count();
+    assert_eq!(leftovers, 0);
+    Ok(())
+}
+
+#[test]
+fn test_uci_file_commit_config() -> Result<()> {
+    let uci_str = "\npackage 'commit'\n\nconfig interface 'lan'\n\toption proto 'static'\n\n";
+    let mut uci = parse_raw_to_uci("commit", uci_str.to_string())?;
+    let _ = std::fs::remove_file(".tmp/commit");
+    assert!(!uci.is_modified());
+    assert!(!commit_config(".tmp", &mut uci)?);
+    assert!(File::open(".tmp/commit").is_err());
+
+    uci.set_option("lan", "proto", vec!["dhcp"])?;
+    assert!(uci.is_modified());
+    assert!(commit_config(".tmp", &mut uci)?);
+    assert!(!uci.is_modified());
+    let mtime = std::fs::metadata(".tmp/commit")?.modified()?;
+
+    assert!(!commit_config(".tmp", &mut uci)?);
+    assert_eq!(std::fs::metadata(".tmp/commit")?.modified()?, mtime);
+    let mut uci = load_config("commit", ".tmp")?;
+    assert_eq!(uci.get_option("lan", "proto")?.1, &vec!["dhcp".to_string()]);
+
+    std::fs::set_permissions(".tmp/commit", std::fs::Permissions::from_mode(0o600))?;
+    uci.set_option("lan", "proto", vec!["static"])?;
+    assert!(commit_config(".tmp", &mut uci)?);
+    let meta = std::fs::metadata(".tmp/commit")?;
+    assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+    Ok(())
+}
+
+#[test]
+fn test_uci_file_save_config_unmodified() -> Result<()> {
+    for (name, uci_str) in [
+        (
+            "edges_bare",
+            "package 'edges_bare'\n\nconfig interface 'lan'\n\toption proto 'static'\n",
+        ),
+        (
+            "edges_padded",
+            "\n\n\nconfig interface 'lan'\n\toption proto 'static'\n\n\n\n",
+        ),
+        ("edges_comment", "config interface 'lan'\n\n# footer"),
+        ("edges_blank", "\n\n"),
+    ] {
+        std::fs::write(format!(".tmp/{}", name), uci_str)?;
+        let uci = load_config(name, ".tmp")?;
+        save_config(".tmp", uci)?;
+        assert_eq!(std::fs::read_to_string(format!(".tmp/{}", name))?, uci_str);
+    }
+
+    let mut uci = load_config("edges_padded", ".tmp")?;
+    uci.set_option("lan", "proto", vec!["dhcp"])?;
+    save_config(".tmp", uci)?;
+    assert_eq!(
+        std::fs::read_to_string(".tmp/edges_padded")?,
+        "\n\n\nconfig interface 'lan'\n\toption proto 'dhcp'\n\n\n\n"
+    );
+    Ok(())
+}
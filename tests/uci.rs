@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs::{create_dir, File, OpenOptions};
 use std::io::{BufWriter, Read, Write};
 use std::os::unix::prelude::OpenOptionsExt;
@@ -26,6 +27,18 @@ fn test_uci_del_section() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_uci_del_section_missing() -> Result<()> {
+    let mut uci: Uci = "config interface 'lan'\n\toption proto 'static'\n".parse()?;
+    assert!(!uci.is_modified());
+    uci.del_section("wan")?;
+    uci.del_section("@rule[0]")?;
+    assert!(!uci.is_modified());
+    uci.del_section("lan")?;
+    assert!(uci.is_modified());
+    Ok(())
+}
+
 #[test]
 fn test_uci_set_option() -> Result<()> {
     let mut uci = Uci::new("test");
@@ -97,6 +110,42 @@ fn test_uci_get_section() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_uci_get_section_type() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.add_section("rule", "")?;
+    assert_eq!(uci.get_section_type("bb")?, Some("ab".to_string()));
+    assert_eq!(uci.get_section_type("@rule[0]")?, Some("rule".to_string()));
+    assert_eq!(uci.get_section_type("missing")?, None);
+    assert_eq!(uci.get_section_type("@rule[1]")?, None);
+    assert!(uci.get_section_type("@rule[x]").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_contains() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("interface", "lan")?;
+    uci.add_section("rule", "")?;
+    uci.set_option("lan", "proto", vec!["static"])?;
+    uci.set_option("@rule[0]", "name", vec!["a"])?;
+
+    assert!(uci.contains_section("lan"));
+    assert!(uci.contains_section("@rule[0]"));
+    assert!(uci.contains_section("@rule[-1]"));
+    assert!(!uci.contains_section("wan"));
+    assert!(!uci.contains_section("@rule[1]"));
+    assert!(!uci.contains_section("@rule[x]"));
+
+    assert!(uci.contains_option("lan", "proto"));
+    assert!(uci.contains_option("@rule[0]", "name"));
+    assert!(!uci.contains_option("lan", "mtu"));
+    assert!(!uci.contains_option("wan", "proto"));
+    assert!(!uci.contains_option("@rule[2]", "name"));
+    Ok(())
+}
+
 #[test]
 fn test_uci_get_all_sections() -> Result<()> {
     let mut uci = Uci::new("test");
@@ -117,6 +166,24 @@ fn test_uci_get_all_sections() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_uci_clear() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let mut uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    assert!(!uci.get_all_sections().is_empty());
+    assert!(!uci.is_modified());
+
+    uci.clear();
+    assert!(uci.get_all_sections().is_empty());
+    assert_eq!(uci.get_package(), "uci_config");
+    assert_eq!(uci.package_or_name(), "uci_config");
+    assert!(uci.is_modified());
+
+    uci.add_section("core", "main")?;
+    assert_eq!(uci.get_all_sections().len(), 1);
+    Ok(())
+}
+
 #[test]
 fn test_uci_del_all() -> Result<()> {
     let mut uci = Uci::new("test");
@@ -159,6 +226,7 @@ fn test_uci_get_section_last() -> Result<()> {
 #[test]
 fn test_uci_set_package() -> Result<()> {
     let mut uci = Uci::new("test");
+    assert_eq!(uci.get_package(), "test");
     uci.set_package("ab")?;
     assert_eq!("ab", uci.get_package());
     Ok(())
@@ -192,18 +260,1741 @@ fn test_uci_write_in() -> Result<()> {
 
     let dir = env::current_dir()?.join(".tmp");
 
-    match create_dir(&dir) {
-        _ => {
-            let file = open_options.open(dir.join("write_in_uci_config"))?;
-            let mut buf = BufWriter::new(file);
-            uci.write_in(&mut buf)?;
-            buf.flush()?;
-            let mut file = File::open(dir.join("write_in_uci_config"))?;
-
-            let mut contents = String::new();
-            file.read_to_string(&mut contents)?;
-            assert_eq!(contents.trim_end(), uci_str.trim_end());
+    let _ = create_dir(&dir);
+    let file = open_options.open(dir.join("write_in_uci_config"))?;
+    let mut buf = BufWriter::new(file);
+    uci.write_in(&mut buf)?;
+    buf.flush()?;
+    let mut file = File::open(dir.join("write_in_uci_config"))?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents.trim_end(), uci_str.trim_end());
+    Ok(())
+}
+
+#[test]
+fn test_uci_drain_sections_of_type() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.add_section("cd", "cc")?;
+    uci.add_section("ab", "dd")?;
+    let drained = uci.drain_sections_of_type("ab");
+    assert_eq!(
+        drained
+            .iter()
+            .map(|sec| sec.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["bb", "dd"]
+    );
+    assert_eq!(uci.get_all("ab"), vec![]);
+    assert_eq!(
+        uci.get_all_sections(),
+        vec![("cd".to_string(), "cc".to_string())]
+    );
+    assert!(uci.drain_sections_of_type("ab").is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_uci_option_count() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    assert_eq!(uci.option_count("main")?, 3);
+    assert_eq!(uci.option_count("ntp")?, 3);
+    assert_eq!(uci.option_count("languages")?, 0);
+    assert_eq!(uci.option_count("@system[0]")?, 5);
+    assert!(uci.option_count("missing").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_try_get_option() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.set_option("bb", "cc", vec!["dd", "ee"])?;
+    assert_eq!(
+        uci.try_get_option("bb", "cc"),
+        Some(&vec!["dd".to_string(), "ee".to_string()])
+    );
+    assert_eq!(uci.try_get_option("bb", "missing"), None);
+    assert_eq!(uci.try_get_option("missing", "cc"), None);
+    assert_eq!(uci.try_get_option("@ab[5]", "cc"), None);
+    Ok(())
+}
+
+#[test]
+fn test_uci_render_template() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    let rendered = uci.render_template(
+        "host=${@system[0].hostname} ntp=${ntp.server[1]} lan=${lan.ipaddr}/${lan.netmask}",
+    )?;
+    assert_eq!(
+        rendered,
+        "host=testhost ntp=1.lede.pool.ntp.org lan=192.168.1.1/255.255.255.0"
+    );
+    assert_eq!(uci.render_template("no references")?, "no references");
+    assert!(uci.render_template("${lan.missing}").is_err());
+    assert!(uci.render_template("${ntp.server[9]}").is_err());
+    assert!(uci.render_template("${lan}").is_err());
+    assert!(uci.render_template("${lan.ipaddr").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_set_from_map() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.set_option("bb", "cc", vec!["old"])?;
+
+    let mut map = HashMap::new();
+    map.insert("cc".to_string(), vec!["dd".to_string()]);
+    map.insert("ee".to_string(), vec!["ff".to_string(), "gg".to_string()]);
+    uci.set_from_map("bb", &map)?;
+
+    assert_eq!(
+        uci.get_option("bb", "cc")?,
+        ("cc".to_string(), &vec!["dd".to_string()])
+    );
+    assert_eq!(
+        uci.get_option("bb", "ee")?,
+        ("ee".to_string(), &vec!["ff".to_string(), "gg".to_string()])
+    );
+    assert!(uci.set_from_map("missing", &map).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_set_section_from_map() -> Result<()> {
+    let mut map = HashMap::new();
+    map.insert("cc".to_string(), vec!["dd".to_string()]);
+
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.set_option("bb", "cc", vec!["old"])?;
+    uci.set_option("bb", "ee", vec!["kept"])?;
+    uci.set_section_from_map("bb", &map, false)?;
+    assert_eq!(uci.get_scalar("bb", "cc")?, "dd");
+    assert_eq!(uci.get_scalar("bb", "ee")?, "kept");
+
+    uci.set_section_from_map("bb", &map, true)?;
+    assert_eq!(uci.get_scalar("bb", "cc")?, "dd");
+    assert!(uci.get_option("bb", "ee").is_err());
+    assert_eq!(uci.option_count("bb")?, 1);
+
+    uci.set_section_from_map("bb", &HashMap::new(), true)?;
+    assert_eq!(uci.option_count("bb")?, 0);
+    assert!(uci.set_section_from_map("missing", &map, true).is_err());
+    assert!(uci.set_section_from_map("missing", &map, false).is_err());
+
+    uci.set_section_from_map("bb", &map, false)?;
+    map.insert("bad name".to_string(), vec!["x".to_string()]);
+    let err = uci.set_section_from_map("bb", &map, true).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidName);
+    assert_eq!(uci.get_scalar("bb", "cc")?, "dd");
+    Ok(())
+}
+
+#[test]
+fn test_uci_sections_iter() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    assert_eq!(uci.sections_iter().count(), uci.get_all_sections().len());
+
+    let ssids: Vec<&str> = uci
+        .sections_of_type("wifi-iface")
+        .map(|sec| sec.get("ssid").unwrap().values[0].as_str())
+        .collect();
+    assert_eq!(ssids, vec!["OpenWrt", "OpenWrt"]);
+    assert_eq!(uci.sections_of_type("internal").count(), 4);
+    assert_eq!(uci.sections_of_type("missing").count(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_scalar() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.set_option("bb", "cc", vec!["dd"])?;
+    uci.set_option("bb", "ee", vec!["ff", "gg"])?;
+    assert_eq!(uci.get_scalar("bb", "cc")?, "dd");
+    assert_eq!(
+        uci.get_scalar("bb", "ee").unwrap_err().message,
+        "option of bb.ee expected a single value, found 2"
+    );
+    assert!(uci.get_scalar("bb", "missing").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_bool() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let mut uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    assert!(uci.get_bool("ntp", "enabled")?);
+    assert!(!uci.get_bool("ntp", "enable_server")?);
+    assert!(!uci.get_bool("guest_radio0", "disabled")?);
+    assert!(uci.get_bool("guest_radio1", "disabled")?);
+
+    uci.set_option("ntp", "enabled", vec!["maybe"])?;
+    assert!(uci.get_bool("ntp", "enabled").is_err());
+    assert!(uci.get_bool("ntp", "missing").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_sections_by_type() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    let internal = uci.sections_by_type("internal");
+    assert_eq!(internal.len(), uci.get_all("internal").len());
+    assert!(internal.iter().all(|sec| sec.sec_type == "internal"));
+    Ok(())
+}
+
+#[test]
+fn test_uci_set_option_if_absent() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+    uci.set_option("lan", "proto", vec!["static"])?;
+
+    assert!(!uci.set_option_if_absent("lan", "proto", vec!["dhcp"])?);
+    assert_eq!(
+        uci.get_option("lan", "proto")?.1,
+        &vec!["static".to_string()]
+    );
+
+    assert!(uci.set_option_if_absent("lan", "dns", vec!["1.1.1.1", "9.9.9.9"])?);
+    let dns = vec!["1.1.1.1".to_string(), "9.9.9.9".to_string()];
+    assert_eq!(uci.get_option("lan", "dns")?.1, &dns);
+    assert!(!uci.set_option_if_absent("lan", "dns", vec!["8.8.8.8"])?);
+
+    let missing = uci.set_option_if_absent("wan", "proto", vec!["dhcp"]);
+    assert!(missing.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_option_parsed() -> Result<()> {
+    let mut uci = Uci::new("wireless");
+    uci.add_section("wifi-device", "radio0")?;
+    uci.set_option("radio0", "channel", vec!["6"])?;
+    uci.set_option("radio0", "htmode", vec!["HT20"])?;
+    uci.add_list_item("radio0", "ips", "10.0.0.1")?;
+    uci.add_list_item("radio0", "ips", "10.0.0.2")?;
+
+    assert_eq!(uci.get_option_parsed::<u8>("radio0", "channel")?, 6);
+    let ip: std::net::IpAddr = uci.get_option_parsed("radio0", "ips")?;
+    assert_eq!(ip.to_string(), "10.0.0.2");
+
+    let err = uci.get_option_parsed::<u8>("radio0", "htmode").unwrap_err();
+    assert_eq!(
+        err.message,
+        "option of radio0.htmode could not be parsed: \"HT20\": invalid digit found in string"
+    );
+    assert!(uci.get_option_parsed::<u8>("radio0", "missing").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_set_bool_and_int() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.set_bool("bb", "enabled", true)?;
+    uci.set_bool("bb", "disabled", false)?;
+    uci.set_int("bb", "mtu", -1500)?;
+    assert!(uci.get_bool("bb", "enabled")?);
+    assert!(!uci.get_bool("bb", "disabled")?);
+    assert_eq!(
+        uci.get_option("bb", "enabled")?,
+        ("enabled".to_string(), &vec!["1".to_string()])
+    );
+    assert_eq!(
+        uci.get_option("bb", "mtu")?,
+        ("mtu".to_string(), &vec!["-1500".to_string()])
+    );
+    assert!(uci.set_bool("missing", "enabled", true).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_parse_streaming() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let mut count = 0;
+    let mut interfaces = vec![];
+    parse_streaming("uci_config", uci_str, |sec| {
+        count += 1;
+        if sec.sec_type == "interface" {
+            interfaces.push(sec.name);
+        }
+        Ok(())
+    })?;
+    assert_eq!(count, 35);
+    assert_eq!(interfaces, vec!["loopback", "lan", "wan", "wan6"]);
+
+    let res = parse_streaming("uci_config", uci_str, |sec| {
+        if sec.sec_type == "system" {
+            Err(Error::new("stop"))
+        } else {
             Ok(())
         }
-    }
+    });
+    assert_eq!(res.unwrap_err().message, "stop");
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_option_ip() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let mut uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    assert_eq!(
+        uci.get_option_ip("lan", "ipaddr")?,
+        "192.168.1.1".parse::<std::net::IpAddr>().unwrap()
+    );
+
+    uci.set_option("lan", "ip6addr", vec!["fd27:70fa:5c1d::1"])?;
+    assert_eq!(
+        uci.get_option_ip("lan", "ip6addr")?,
+        "fd27:70fa:5c1d::1".parse::<std::net::IpAddr>().unwrap()
+    );
+
+    uci.set_option("lan", "dns", vec!["1.1.1.1", "2606:4700:4700::1111"])?;
+    assert_eq!(uci.get_option_ips("lan", "dns")?.len(), 2);
+
+    let err = uci.get_option_ip("lan", "proto").unwrap_err();
+    assert!(err
+        .message
+        .starts_with("option of lan.proto is not a valid ip address"));
+    uci.set_option("lan", "dns", vec!["1.1.1.1", "dns.example"])?;
+    assert!(uci.get_option_ips("lan", "dns").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_rename_section() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.add_section("ab", "cc")?;
+    uci.add_section("ab", "")?;
+    uci.set_option("bb", "dd", vec!["ee"])?;
+    uci.set_option("bb", "ff", vec!["gg", "hh"])?;
+
+    uci.rename_section("bb", "zz")?;
+    assert!(uci.get_section("bb").is_err());
+    assert_eq!(uci.get_section("zz")?, ("ab".to_string(), "zz".to_string()));
+    assert_eq!(
+        uci.get_all_options("zz")?,
+        vec![
+            ("dd".to_string(), &vec!["ee".to_string()]),
+            ("ff".to_string(), &vec!["gg".to_string(), "hh".to_string()])
+        ]
+    );
+    assert_eq!(uci.get_all("ab")[0], ("ab".to_string(), "zz".to_string()));
+
+    uci.rename_section("@ab[2]", "yy")?;
+    assert_eq!(uci.get_section("yy")?, ("ab".to_string(), "yy".to_string()));
+
+    assert!(uci.rename_section("missing", "xx").is_err());
+    assert!(uci.rename_section("zz", "cc").is_err());
+    assert!(uci.rename_section("zz", "").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_copy_section() -> Result<()> {
+    let mut uci: Uci = "config wifi-iface 'wlan0'\n\toption ssid 'home'\n\tlist maclist 'aa'\n\tlist maclist 'bb'\n"
+        .parse()?;
+    uci.copy_section("wlan0", "wlan1")?;
+    assert!(uci.is_modified());
+    assert_eq!(
+        uci.get_section("wlan1")?,
+        ("wifi-iface".to_string(), "wlan1".to_string())
+    );
+    assert_eq!(uci.get_all_options("wlan1")?, uci.get_all_options("wlan0")?);
+
+    uci.set_option("wlan1", "ssid", vec!["guest"])?;
+    uci.add_list_item("wlan1", "maclist", "cc")?;
+    assert_eq!(
+        uci.get_option("wlan0", "ssid")?.1,
+        &vec!["home".to_string()]
+    );
+    assert_eq!(uci.get_option("wlan0", "maclist")?.1.len(), 2);
+    assert_eq!(uci.get_option("wlan1", "maclist")?.1.len(), 3);
+
+    uci.copy_section("@wifi-iface[0]", "wlan2")?;
+    assert_eq!(
+        uci.get_option("wlan2", "ssid")?.1,
+        &vec!["home".to_string()]
+    );
+
+    let err = uci.copy_section("missing", "wlan3").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let err = uci.copy_section("wlan0", "wlan1").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert!(uci.copy_section("wlan0", "").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_validate_references() -> Result<()> {
+    let mut uci = Uci::new("wireless");
+    uci.add_section("wifi-device", "wl0")?;
+    uci.add_section("wifi-iface", "")?;
+    uci.set_option("@wifi-iface[0]", "device", vec!["wl0"])?;
+    uci.add_section("wifi-iface", "guest")?;
+
+    let rules = vec![ReferenceRule::new("wifi-iface", "device", "wifi-device")];
+    uci.validate_references(&rules)?;
+
+    uci.set_option("guest", "device", vec!["wl1"])?;
+    assert_eq!(
+        uci.validate_references(&rules).unwrap_err().message,
+        "option of guest.device references missing wifi-device section 'wl1'"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_rename_option() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    uci.set_option("bb", "cc", vec!["dd"])?;
+    uci.set_option("bb", "ee", vec!["ff", "gg"])?;
+
+    uci.rename_option("bb", "ee", "hh")?;
+    assert!(uci.get_option("bb", "ee").is_err());
+    assert_eq!(
+        uci.get_all_options("bb")?,
+        vec![
+            ("cc".to_string(), &vec!["dd".to_string()]),
+            ("hh".to_string(), &vec!["ff".to_string(), "gg".to_string()])
+        ]
+    );
+    uci.for_each("ab", |sec| {
+        assert_eq!(sec.get("hh").unwrap().opt_type, UciOptionType::TypeList);
+    });
+
+    assert!(uci.rename_option("bb", "hh", "cc").is_err());
+    assert!(uci.rename_option("bb", "missing", "zz").is_err());
+    assert!(uci.rename_option("missing", "cc", "zz").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_sections_sorted_by_option() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "wan")?;
+    uci.set_option("wan", "metric", vec!["20"])?;
+    uci.add_section("interface", "lan")?;
+    uci.add_section("interface", "wwan")?;
+    uci.set_option("wwan", "metric", vec!["10"])?;
+    uci.add_section("device", "br")?;
+    uci.set_option("br", "metric", vec!["00"])?;
+
+    let names: Vec<&str> = uci
+        .sections_sorted_by_option("interface", "metric")
+        .iter()
+        .map(|sec| sec.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["wwan", "wan", "lan"]);
+    Ok(())
+}
+
+#[test]
+fn test_uci_add_and_del_list_item() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let mut uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    uci.add_list_item("ntp", "server", "4.lede.pool.ntp.org")?;
+    uci.add_list_item("ntp", "server", "0.lede.pool.ntp.org")?;
+    let (_, servers) = uci.get_option("ntp", "server")?;
+    assert_eq!(servers.len(), 5);
+    assert_eq!(servers[4], "4.lede.pool.ntp.org");
+
+    uci.del_list_item("ntp", "server", "1.lede.pool.ntp.org")?;
+    let (_, servers) = uci.get_option("ntp", "server")?;
+    assert_eq!(
+        servers,
+        &vec![
+            "0.lede.pool.ntp.org".to_string(),
+            "2.lede.pool.ntp.org".to_string(),
+            "3.lede.pool.ntp.org".to_string(),
+            "4.lede.pool.ntp.org".to_string()
+        ]
+    );
+
+    uci.add_list_item("lan", "dns", "1.1.1.1")?;
+    assert_eq!(
+        uci.get_option("lan", "dns")?,
+        ("dns".to_string(), &vec!["1.1.1.1".to_string()])
+    );
+    uci.del_list_item("lan", "dns", "1.1.1.1")?;
+    assert_eq!(uci.get_option("lan", "dns")?, ("dns".to_string(), &vec![]));
+
+    assert!(uci.add_list_item("missing", "dns", "1.1.1.1").is_err());
+    assert!(uci.del_list_item("lan", "missing", "1.1.1.1").is_err());
+
+    uci.del_list_item("lan", "proto", "static")?;
+    assert_eq!(
+        uci.get_option("lan", "proto")?,
+        ("proto".to_string(), &vec![])
+    );
+    let mut map = HashMap::new();
+    map.insert("ifname".to_string(), vec![]);
+    uci.set_from_map("lan", &map)?;
+    let reparsed: Uci = uci.to_string().parse()?;
+    assert!(reparsed.get_option("lan", "proto").is_err());
+    assert!(reparsed.get_option("lan", "ifname").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_compact_output() -> Result<()> {
+    assert_eq!(
+        compact_output("\n\n\npackage 'a'\n\n \n\t\nconfig b\n\n"),
+        "\npackage 'a'\n\nconfig b\n\n"
+    );
+
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    let mut first = BufWriter::new(Vec::new());
+    uci.write_in(&mut first)?;
+    let first = first.into_inner().unwrap();
+
+    let uci = parse_raw_to_uci("uci_config", String::from_utf8_lossy(&first).to_string())?;
+    let mut second = BufWriter::new(Vec::new());
+    uci.write_in(&mut second)?;
+    let second = second.into_inner().unwrap();
+
+    let blank_lines = |buf: &[u8]| {
+        String::from_utf8_lossy(buf)
+            .lines()
+            .filter(|line| line.trim().is_empty())
+            .count()
+    };
+    assert_eq!(blank_lines(&first), blank_lines(&second));
+    assert_eq!(first, second);
+    Ok(())
+}
+
+#[test]
+fn test_uci_move_section() -> Result<()> {
+    let mut uci = Uci::new("firewall");
+    uci.add_section("rule", "")?;
+    uci.set_option("@rule[0]", "name", vec!["first"])?;
+    uci.add_section("rule", "")?;
+    uci.set_option("@rule[1]", "name", vec!["second"])?;
+    uci.add_section("defaults", "defaults")?;
+
+    uci.move_section("@rule[1]", 0)?;
+    assert_eq!(
+        uci.get_option_first("@rule[0]", "name")?,
+        ("name".to_string(), Some("second".to_string()))
+    );
+
+    uci.move_section("defaults", 0)?;
+    uci.move_section("@rule[0]", 100)?;
+    assert_eq!(
+        uci.get_all_sections(),
+        vec![
+            ("defaults".to_string(), "defaults".to_string()),
+            ("rule".to_string(), "@rule[0]".to_string()),
+            ("rule".to_string(), "@rule[1]".to_string())
+        ]
+    );
+    assert_eq!(
+        uci.get_option_first("@rule[1]", "name")?,
+        ("name".to_string(), Some("second".to_string()))
+    );
+
+    assert!(uci.move_section("missing", 0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_bool_or_from() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("globals", "globals")?;
+    uci.set_option("globals", "ipv6", vec!["0"])?;
+    uci.add_section("interface", "lan")?;
+    uci.set_option("lan", "ipv6", vec!["1"])?;
+    uci.add_section("interface", "wan")?;
+
+    assert!(uci.get_bool_or_from("lan", "globals", "ipv6", false));
+    assert!(!uci.get_bool_or_from("wan", "globals", "ipv6", true));
+    assert!(uci.get_bool_or_from("wan", "globals", "delegate", true));
+    assert!(!uci.get_bool_or_from("missing", "missing", "ipv6", false));
+    Ok(())
+}
+
+#[test]
+fn test_uci_to_string() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    let mut buf = BufWriter::new(Vec::new());
+    uci.write_in(&mut buf)?;
+    let written = String::from_utf8(buf.into_inner().unwrap()).unwrap();
+    assert_eq!(uci.to_string(), written);
+    assert_eq!(written, uci_str);
+    Ok(())
+}
+
+#[test]
+fn test_uci_merge_from_with_comments() -> Result<()> {
+    let mut uci = parse_raw_to_uci(
+        "network",
+        "# lan settings\nconfig interface 'lan'\n\t# static address\n\toption proto 'static'\n"
+            .to_string(),
+    )?;
+    let other = parse_raw_to_uci(
+        "network",
+        "# overridden\nconfig interface 'lan'\n\t# ignored\n\toption proto 'dhcp'\n\t# new option\n\toption mtu '1400'\n\n# wan settings\nconfig interface 'wan'\n"
+            .to_string(),
+    )?;
+    uci.merge_from_with_comments(&other);
+
+    assert_eq!(
+        uci.to_string(),
+        "# lan settings\nconfig interface 'lan'\n\t# static address\n\toption proto 'dhcp'\n\t# new option\n\toption mtu '1400'\n\n# wan settings\nconfig interface 'wan'\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_merge_from_reporting() -> Result<()> {
+    let mut uci = parse_raw_to_uci(
+        "network",
+        "config interface 'lan'\n\toption proto 'static'\n\tlist dns '1.1.1.1'\n".to_string(),
+    )?;
+    let other = parse_raw_to_uci(
+        "network",
+        "config interface 'lan'\n\toption proto 'dhcp'\n\tlist dns '9.9.9.9'\nconfig interface 'wan'\n"
+            .to_string(),
+    )?;
+    let changes = uci.merge_from_reporting(&other)?;
+    assert_eq!(
+        changes,
+        vec![
+            ConfigChange::OptionChanged {
+                section: "lan".to_string(),
+                option: "proto".to_string(),
+                from: Some("static".to_string()),
+                to: Some("dhcp".to_string()),
+            },
+            ConfigChange::ListChanged {
+                section: "lan".to_string(),
+                option: "dns".to_string(),
+                from: vec!["1.1.1.1".to_string()],
+                to: vec!["1.1.1.1".to_string(), "9.9.9.9".to_string()],
+            },
+            ConfigChange::SectionAdded {
+                section: "wan".to_string(),
+                typ: "interface".to_string(),
+            },
+        ]
+    );
+    assert!(uci.merge_from_reporting(&other)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_uci_merge_from_str() -> Result<()> {
+    let mut uci = parse_raw_to_uci(
+        "network",
+        "config interface 'lan'\n\toption proto 'static'\n\toption ipaddr '10.0.0.1'\n\tlist dns '1.1.1.1'\n".to_string(),
+    )?;
+    assert!(!uci.is_modified());
+
+    uci.merge_from_str("config interface 'lan'\n\toption ipaddr '10.0.0.2'\n")?;
+    assert!(uci.is_modified());
+    assert_eq!(
+        uci.get_option("lan", "ipaddr")?.1,
+        &vec!["10.0.0.2".to_string()]
+    );
+    assert_eq!(
+        uci.get_option("lan", "proto")?.1,
+        &vec!["static".to_string()]
+    );
+    assert_eq!(
+        uci.get_option("lan", "dns")?.1,
+        &vec!["1.1.1.1".to_string()]
+    );
+
+    uci.merge_from_str("config interface 'lan'\n\tlist dns '9.9.9.9'\nconfig interface 'wan'\n\toption proto 'dhcp'\n")?;
+    assert_eq!(uci.get_option("lan", "dns")?.1.len(), 2);
+    assert_eq!(uci.get_option("wan", "proto")?.1, &vec!["dhcp".to_string()]);
+
+    let before = uci.to_string();
+    let err = uci.merge_from_str("config interface 'lan\n").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Parse);
+    assert_eq!(uci.to_string(), before);
+    Ok(())
+}
+
+#[test]
+fn test_uci_from_str() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci: Uci = uci_str.parse()?;
+    assert_eq!(uci.get_package(), "uci_config");
+    assert_eq!(uci.to_string().parse::<Uci>()?.to_string(), uci.to_string());
+
+    let uci: Uci = "config interface 'lan'\n\toption proto 'static'\n".parse()?;
+    assert_eq!(uci.package_or_name(), "<memory>");
+    assert!("config 'broken".parse::<Uci>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_list_entry() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("ab", "bb")?;
+    let list = uci.list_entry("bb", "cc")?;
+    for v in ["dd", "ee", "ff"] {
+        list.push_value(v);
+    }
+    assert_eq!(
+        uci.get_option("bb", "cc")?,
+        (
+            "cc".to_string(),
+            &vec!["dd".to_string(), "ee".to_string(), "ff".to_string()]
+        )
+    );
+
+    uci.set_option("bb", "gg", vec!["hh"])?;
+    uci.list_entry("bb", "gg")?.push_value("ii");
+    assert_eq!(
+        uci.to_string(),
+        "\npackage 'test'\n\nconfig ab 'bb'\n\tlist cc 'dd'\n\tlist cc 'ee'\n\tlist cc 'ff'\n\tlist gg 'hh'\n\tlist gg 'ii'\n\n"
+    );
+    assert!(uci.list_entry("missing", "cc").is_err());
+
+    let mut uci: Uci = "config ab 'bb'\n".parse()?;
+    assert!(uci.list_entry("missing", "cc").is_err());
+    assert!(!uci.is_modified());
+    Ok(())
+}
+
+#[test]
+fn test_uci_section_types() -> Result<()> {
+    let mut uci = Uci::new("test");
+    assert!(uci.section_types().is_empty());
+    uci.add_section("interface", "lan")?;
+    uci.add_section("rule", "")?;
+    uci.add_section("interface", "wan")?;
+    uci.add_section("zone", "")?;
+    uci.add_section("rule", "")?;
+    assert_eq!(uci.section_types(), vec!["interface", "rule", "zone"]);
+    Ok(())
+}
+
+#[test]
+fn test_uci_show() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+    uci.set_option("lan", "ipaddr", vec!["192.168.1.1"])?;
+    uci.add_section("rule", "")?;
+    uci.add_list_item("@rule[0]", "network", "lan")?;
+    uci.add_list_item("@rule[0]", "network", "wan")?;
+
+    let shown: Vec<String> = uci
+        .show()
+        .into_iter()
+        .map(|(key, value)| format!("{}='{}'", key, value))
+        .collect();
+    assert_eq!(
+        shown,
+        vec![
+            "network.lan='interface'",
+            "network.lan.ipaddr='192.168.1.1'",
+            "network.@rule[0]='rule'",
+            "network.@rule[0].network.0='lan'",
+            "network.@rule[0].network.1='wan'",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_write_types() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.set_package("network")?;
+    uci.add_section("interface", "lan")?;
+    uci.set_option("lan", "proto", vec!["static"])?;
+    uci.add_section("rule", "")?;
+    uci.set_option("@rule[0]", "target", vec!["ACCEPT"])?;
+    uci.add_section("interface", "wan")?;
+    uci.set_option("wan", "proto", vec!["dhcp"])?;
+
+    let mut out = vec![];
+    uci.write_types(&mut out, &["interface"])?;
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(
+        out,
+        "\npackage 'network'\n\nconfig interface 'lan'\n\toption proto 'static'\n\nconfig interface 'wan'\n\toption proto 'dhcp'\n\n"
+    );
+
+    let parsed: Uci = out.parse()?;
+    assert_eq!(parsed.get_package(), "network");
+    assert_eq!(parsed.section_types(), vec!["interface"]);
+    assert_eq!(
+        parsed.get_option("wan", "proto")?.1,
+        &vec!["dhcp".to_string()]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_export_filtered() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.set_package("network")?;
+    uci.add_section("interface", "wan")?;
+    uci.set_option("wan", "proto", vec!["pppoe"])?;
+    uci.set_option("wan", "password", vec!["secret"])?;
+    uci.add_list_item("wan", "dns", "1.1.1.1")?;
+    uci.add_section("rule", "")?;
+    uci.set_option("@rule[0]", "target", vec!["ACCEPT"])?;
+
+    let out = uci.export_filtered(&["interface"], Some(&["proto", "dns"]));
+    assert_eq!(
+        out,
+        "\npackage 'network'\n\nconfig interface 'wan'\n\toption proto 'pppoe'\n\tlist dns '1.1.1.1'\n\n"
+    );
+    let parsed: Uci = out.parse()?;
+    assert_eq!(parsed.section_types(), vec!["interface"]);
+    assert!(parsed.get_option("wan", "password").is_err());
+    assert_eq!(
+        parsed.get_option("wan", "proto")?.1,
+        &vec!["pppoe".to_string()]
+    );
+
+    let out = uci.export_filtered(&["interface", "rule"], None);
+    assert_eq!(out, uci.to_string());
+    assert!(!uci.export_filtered(&[], None).contains("config"));
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_option_ci() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("system", "main")?;
+    uci.set_option("main", "lang", vec!["en"])?;
+
+    assert_eq!(
+        uci.get_option_ci("main", "Lang")?,
+        ("lang".to_string(), vec!["en".to_string()])
+    );
+    assert!(uci.get_option("main", "Lang").is_err());
+    assert!(uci.get_option_ci("main", "zone").is_err());
+    assert!(uci.get_option_ci("missing", "lang").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_write_escaped_values() -> Result<()> {
+    let mut uci = Uci::new("wireless");
+    uci.add_section("wifi-iface", "ap")?;
+    uci.set_option("ap", "ssid", vec!["Bob's \"fast\" AP"])?;
+    uci.set_option("ap", "key", vec![r"it's a\b"])?;
+    uci.set_option("ap", "path", vec![r"C:\"])?;
+    uci.set_option("ap", "eol", vec!["crlf\r\n"])?;
+    uci.add_list_item("ap", "note", "line1\nline2")?;
+    uci.add_list_item("ap", "note", "plain value")?;
+
+    let output = uci.to_string();
+    assert!(output.contains(r#"option ssid "Bob's \"fast\" AP""#));
+    assert!(output.contains("list note 'plain value'"));
+    assert!(output.contains(r#"option path "C:\\""#));
+    assert!(output.contains(r#"option eol "crlf\r\n""#));
+
+    let parsed: Uci = output.parse()?;
+    assert_eq!(
+        parsed.get_option("ap", "ssid")?.1,
+        &vec!["Bob's \"fast\" AP".to_string()]
+    );
+    assert_eq!(
+        parsed.get_option("ap", "key")?.1,
+        &vec![r"it's a\b".to_string()]
+    );
+    assert_eq!(
+        parsed.get_option("ap", "path")?.1,
+        &vec![r"C:\".to_string()]
+    );
+    assert_eq!(
+        parsed.get_option("ap", "eol")?.1,
+        &vec!["crlf\r\n".to_string()]
+    );
+    assert_eq!(
+        parsed.get_option("ap", "note")?.1,
+        &vec!["line1\nline2".to_string(), "plain value".to_string()]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_write_escaped_names() -> Result<()> {
+    let uci: Uci = "package \"o'k\"\n\nconfig foo \"it's\"\n\toption a 'b'\n".parse()?;
+    let output = uci.to_string();
+    assert!(output.contains("package \"o'k\"\n"));
+    assert!(output.contains("config foo \"it's\"\n"));
+
+    let parsed: Uci = output.parse()?;
+    assert_eq!(parsed.get_package(), "o'k");
+    assert_eq!(
+        parsed.get_section("it's")?,
+        ("foo".to_string(), "it's".to_string())
+    );
+    assert_eq!(parsed.get_option("it's", "a")?.1, &vec!["b".to_string()]);
+    Ok(())
+}
+
+#[test]
+fn test_uci_write_to() -> Result<()> {
+    let uci_str = "config interface 'lan'\n\toption proto 'static'\n\tlist dns '1.1.1.1'\n";
+    let uci: Uci = uci_str.parse()?;
+    let mut out = Vec::new();
+    uci.write_to(&mut out)?;
+    assert_eq!(out, uci_str.as_bytes());
+
+    let dir = env::current_dir()?.join(".tmp");
+    let _ = create_dir(&dir);
+    uci.write_to(File::create(dir.join("write_to_uci_config"))?)?;
+    assert_eq!(std::fs::read(dir.join("write_to_uci_config"))?, out);
+    Ok(())
+}
+
+#[test]
+fn test_uci_write_in_with() -> Result<()> {
+    let uci: Uci = "package 'net'\nconfig interface 'lan'\n\t# addressing\n\toption proto 'static'\n\toption name 'say \"hi\"'\n\tlist path 'C:\\temp'\n"
+        .parse()?;
+    let write = |opts: &WriteOptions| -> Result<String> {
+        let mut buf = BufWriter::new(Vec::new());
+        uci.write_in_with(&mut buf, opts)?;
+        Ok(String::from_utf8(buf.into_inner().unwrap()).unwrap())
+    };
+
+    let mut buf = BufWriter::new(Vec::new());
+    uci.write_in(&mut buf)?;
+    assert_eq!(
+        write(&WriteOptions::default())?,
+        String::from_utf8(buf.into_inner().unwrap()).unwrap()
+    );
+
+    let opts = WriteOptions {
+        indent: "  ".to_string(),
+        quote: '"',
+    };
+    let out = write(&opts)?;
+    assert_eq!(
+        out,
+        "package \"net\"\n\nconfig interface \"lan\"\n  # addressing\n  option proto \"static\"\n  option name \"say \\\"hi\\\"\"\n  list path \"C:\\\\temp\"\n"
+    );
+    let reparsed: Uci = out.parse()?;
+    assert_eq!(
+        reparsed.get_all_options("lan")?,
+        uci.get_all_options("lan")?
+    );
+
+    let opts = WriteOptions {
+        quote: '`',
+        ..WriteOptions::default()
+    };
+    assert!(write(&opts).is_err());
+    let opts = WriteOptions {
+        indent: "->".to_string(),
+        ..WriteOptions::default()
+    };
+    assert!(write(&opts).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_to_bytes() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    assert_eq!(uci.to_bytes()?, uci.to_string().into_bytes());
+    assert_eq!(uci.to_bytes()?, uci_str.as_bytes());
+    assert_eq!(Uci::new("empty").to_bytes()?, b"\npackage 'empty'\n\n");
+    Ok(())
+}
+
+#[test]
+fn test_uci_parse_collect() {
+    let input = "package 'net'\n\nconfig interface 'lan'\n\toption proto 'static'\n\nconfig interface 'broken\n\toption proto 'dhcp'\n\nconfig interface 'wan'\n\toption proto 'dhcp'\n\toption 'bad' x\n\nconfig rule\n\toption name 'ok'\n";
+    let (cfg, errors) = uci_parse_collect("collect", input.to_string());
+    let cfg = cfg.unwrap();
+    assert_eq!(cfg.pkg_name, "net");
+    assert_eq!(
+        cfg.sections
+            .iter()
+            .map(|sec| cfg.get_section_name(sec))
+            .collect::<Vec<String>>(),
+        vec!["lan", "@rule[0]"]
+    );
+    assert_eq!(
+        errors
+            .iter()
+            .map(|err| err.line)
+            .collect::<Vec<Option<usize>>>(),
+        vec![Some(6), Some(11)]
+    );
+    let (start, end) = errors[1].span.unwrap();
+    assert_eq!(&input[start..end], "'bad'");
+
+    let (cfg, errors) = uci_parse_collect("collect", "config 'broken\n".to_string());
+    assert!(cfg.is_none());
+    assert_eq!(errors.len(), 1);
+
+    let (cfg, errors) = uci_parse_collect("collect", "config rule\n\toption a 'b'\n".to_string());
+    assert_eq!(cfg.unwrap().sections.len(), 1);
+    assert!(errors.is_empty());
+    let input = "package 'net'\n\nconfig interface 'lan'\n\npackage 'fw'\n\nconfig rule\n\toption name 'ok'\n\toption 'bad' x\n\nconfig zone 'wan'\n\toption input 'REJECT'\n";
+    let (cfg, errors) = uci_parse_collect("collect", input.to_string());
+    let cfg = cfg.unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(cfg.get("@rule[0]").is_err());
+    assert_eq!(cfg.get("lan").unwrap().unwrap().package, "");
+    assert_eq!(cfg.get("wan").unwrap().unwrap().package, "fw");
+}
+
+#[test]
+fn test_uci_prepend_section() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("route", "")?;
+    uci.set_option("@route[0]", "target", vec!["10.0.0.0"])?;
+    uci.add_section("interface", "lan")?;
+
+    let mut default_route = UciSection::new("route", "");
+    default_route.add(UciOption::new(
+        "target",
+        UciOptionType::TypeOption,
+        vec!["0.0.0.0".to_string()],
+    ));
+    uci.prepend_section(default_route)?;
+    assert_eq!(uci.get_scalar("@route[0]", "target")?, "0.0.0.0");
+    assert_eq!(uci.get_scalar("@route[1]", "target")?, "10.0.0.0");
+
+    let err = uci
+        .prepend_section(UciSection::new("interface", "lan"))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    let err = uci
+        .prepend_section(UciSection::new("bad type", ""))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidName);
+    let err = uci
+        .prepend_section(UciSection::new("interface", "bad.name"))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidName);
+
+    uci.prepend_section(UciSection::new("interface", "wan"))?;
+    assert_eq!(
+        uci.get_all_sections(),
+        vec![
+            ("interface".to_string(), "wan".to_string()),
+            ("route".to_string(), "@route[0]".to_string()),
+            ("route".to_string(), "@route[1]".to_string()),
+            ("interface".to_string(), "lan".to_string()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_first_index_of_type() -> Result<()> {
+    let mut uci = Uci::new("firewall");
+    uci.add_section("defaults", "")?;
+    uci.add_section("zone", "lan")?;
+    uci.add_section("rule", "")?;
+    uci.add_section("zone", "wan")?;
+    assert_eq!(uci.first_index_of_type("defaults"), Some(0));
+    assert_eq!(uci.first_index_of_type("zone"), Some(1));
+    assert_eq!(uci.first_index_of_type("rule"), Some(2));
+    assert_eq!(uci.first_index_of_type("forwarding"), None);
+    Ok(())
+}
+
+#[test]
+fn test_uci_swap_sections() -> Result<()> {
+    let mut uci = Uci::new("firewall");
+    uci.add_section("rule", "")?;
+    uci.set_option("@rule[0]", "name", vec!["first"])?;
+    uci.add_section("zone", "lan")?;
+    uci.add_section("rule", "")?;
+    uci.set_option("@rule[1]", "name", vec!["second"])?;
+
+    uci.swap_sections(0, 2)?;
+    assert_eq!(uci.get_scalar("@rule[0]", "name")?, "second");
+    assert_eq!(uci.get_scalar("@rule[1]", "name")?, "first");
+    assert_eq!(uci.first_index_of_type("zone"), Some(1));
+
+    uci.swap_sections(1, 1)?;
+    assert_eq!(uci.first_index_of_type("zone"), Some(1));
+    assert!(uci.swap_sections(0, 3).is_err());
+    assert!(uci.swap_sections(3, 0).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_uci_option_json() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+    uci.set_option("lan", "proto", vec!["static"])?;
+    uci.add_list_item("lan", "dns", "1.1.1.1")?;
+    uci.add_list_item("lan", "dns", "8.8.8.8")?;
+
+    assert_eq!(
+        uci.option_json("lan", "proto")?,
+        serde_json::json!("static")
+    );
+    assert_eq!(
+        uci.option_json("lan", "dns")?,
+        serde_json::json!(["1.1.1.1", "8.8.8.8"])
+    );
+    assert!(uci.option_json("lan", "missing").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_count_values_matching() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("wireguard_peer", "peer")?;
+    for ip in ["10.0.0.2/32", "192.168.1.0/24", "10.8.0.0/16"] {
+        uci.add_list_item("peer", "allowed_ips", ip)?;
+    }
+    uci.set_option("peer", "host", vec!["10.0.0.1"])?;
+
+    let in_ten = |v: &str| v.starts_with("10.");
+    assert_eq!(uci.count_values_matching("peer", "allowed_ips", in_ten)?, 2);
+    assert_eq!(uci.count_values_matching("peer", "host", in_ten)?, 1);
+    assert_eq!(uci.count_values_matching("peer", "host", |_| false)?, 0);
+    assert!(uci.count_values_matching("peer", "dns", in_ten).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_upsert() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.upsert("interface", "lan", "proto", vec!["static"])?;
+    uci.upsert("interface", "lan", "ipaddr", vec!["192.168.1.1"])?;
+    uci.upsert("interface", "lan", "proto", vec!["dhcp"])?;
+
+    assert_eq!(
+        uci.get_all_sections(),
+        vec![("interface".to_string(), "lan".to_string())]
+    );
+    assert_eq!(uci.get_scalar("lan", "proto")?, "dhcp");
+    assert_eq!(uci.get_scalar("lan", "ipaddr")?, "192.168.1.1");
+
+    assert!(uci.upsert("device", "lan", "name", vec!["br-lan"]).is_err());
+    assert!(uci.upsert("interface", "", "proto", vec!["dhcp"]).is_err());
+    assert_eq!(uci.get_section("lan")?.0, "interface");
+    Ok(())
+}
+
+#[test]
+fn test_uci_list_ref_or() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+    uci.add_list_item("lan", "dns", "1.1.1.1")?;
+    uci.add_list_item("lan", "dns", "9.9.9.9")?;
+
+    let default = vec!["8.8.8.8".to_string()];
+    assert_eq!(
+        uci.list_ref_or("lan", "dns", &default),
+        ["1.1.1.1".to_string(), "9.9.9.9".to_string()]
+    );
+    assert_eq!(uci.list_ref_or("lan", "missing", &default), &default[..]);
+    assert_eq!(uci.list_ref_or("wan", "dns", &default), &default[..]);
+    assert!(uci.list_ref_or("@rule[0]", "dns", &[]).is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_option_last_nonempty() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+    for value in ["1.1.1.1", "9.9.9.9", "", ""] {
+        uci.add_list_item("lan", "dns", value)?;
+    }
+    uci.add_list_item("lan", "empty", "")?;
+
+    let last = uci.get_option_last_nonempty("lan", "dns")?;
+    assert_eq!(last, Some("9.9.9.9".to_string()));
+    assert_eq!(uci.get_option_last_nonempty("lan", "empty")?, None);
+    assert!(uci.get_option_last_nonempty("lan", "missing").is_err());
+    assert!(uci.get_option_last_nonempty("wan", "dns").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_remove_option() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+    uci.add_section("interface", "wan")?;
+    uci.add_list_item("lan", "dns", "1.1.1.1")?;
+    uci.add_list_item("lan", "dns", "9.9.9.9")?;
+
+    let opt = uci.remove_option("lan", "dns")?.unwrap();
+    assert_eq!(opt.opt_type, UciOptionType::TypeList);
+    assert!(uci.get_option("lan", "dns").is_err());
+    assert_eq!(uci.remove_option("lan", "dns")?, None);
+
+    for v in opt.values.iter() {
+        uci.add_list_item("wan", &opt.name, v)?;
+    }
+    assert_eq!(uci.get_option("wan", "dns")?.1, &opt.values);
+    assert!(uci.remove_option("missing", "dns").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_write_sorted() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("zone", "wan")?;
+    uci.set_option("wan", "name", vec!["wan"])?;
+    uci.add_section("interface", "lan")?;
+    uci.set_option("lan", "proto", vec!["static"])?;
+    uci.add_list_item("lan", "dns", "9.9.9.9")?;
+    uci.add_list_item("lan", "dns", "1.1.1.1")?;
+    uci.add_section("rule", "")?;
+    uci.add_section("interface", "guest")?;
+    let original = uci.to_string();
+
+    let mut buf = BufWriter::new(vec![]);
+    uci.write_sorted(&mut buf)?;
+    let sorted = String::from_utf8(buf.into_inner().unwrap()).unwrap();
+    assert_eq!(
+        sorted,
+        "\npackage 'network'\n\nconfig interface 'guest'\n\nconfig interface 'lan'\n\tlist dns '9.9.9.9'\n\tlist dns '1.1.1.1'\n\toption proto 'static'\n\nconfig rule\n\nconfig zone 'wan'\n\toption name 'wan'\n\n"
+    );
+    assert_eq!(uci.to_string(), original);
+    Ok(())
+}
+
+#[test]
+fn test_uci_parse_section_body() -> Result<()> {
+    let body = "\toption proto 'static'\n\toption ipaddr '192.168.1.1'";
+    let sec = Uci::parse_section_body("interface", "lan", body)?;
+    assert_eq!(sec.sec_type, "interface");
+    assert_eq!(sec.name, "lan");
+    assert_eq!(sec.options.len(), 2);
+    assert_eq!(sec.get("proto").unwrap().values, vec!["static".to_string()]);
+    assert_eq!(sec.get("ipaddr").unwrap().values, vec!["192.168.1.1"]);
+
+    let sec = Uci::parse_section_body("rule", "", "list proto 'tcp'\nlist proto 'udp'\n")?;
+    assert_eq!(sec.name, "");
+    assert_eq!(sec.get("proto").unwrap().values.len(), 2);
+
+    assert!(Uci::parse_section_body("rule", "", "config zone\n").is_err());
+    let err = Uci::parse_section_body("interface", "lan", "option a 'b'\nconfig interface 'lan'\n")
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::Parse);
+    assert!(Uci::parse_section_body("rule", "", "package 'net'\n").is_err());
+    let err = Uci::parse_section_body("rule", "", "option a 'b'\noption c 'broken\n")
+        .err()
+        .unwrap();
+    assert_eq!(err.line, Some(2));
+    assert!(err.message.contains("<memory>:2:"));
+
+    let err = Uci::parse_section_body("bad type", "", "option a 'b'\n")
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidName);
+    let err = Uci::parse_section_body("interface", "it's", "option a 'b'\n")
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidName);
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_option_value_at() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+    for dns in ["1.1.1.1", "8.8.8.8", "9.9.9.9"] {
+        uci.add_list_item("lan", "dns", dns)?;
+    }
+    uci.set_option("lan", "proto", vec!["static"])?;
+
+    assert_eq!(
+        uci.get_option_value_at("lan", "dns", 2)?,
+        Some("9.9.9.9".to_string())
+    );
+    assert_eq!(uci.get_option_value_at("lan", "dns", 3)?, None);
+    assert_eq!(
+        uci.get_option_value_at("lan", "proto", 0)?,
+        Some("static".to_string())
+    );
+    assert!(uci.get_option_value_at("lan", "gateway", 0).is_err());
+    assert!(uci.get_option_value_at("wan", "dns", 0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_can_apply() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+    uci.set_option("lan", "proto", vec!["static"])?;
+    let original = uci.to_string();
+
+    let patch = vec![
+        Change::AddSection {
+            typ: "interface".to_string(),
+            name: "wan".to_string(),
+        },
+        Change::SetOption {
+            section: "wan".to_string(),
+            option: "proto".to_string(),
+            values: vec!["dhcp".to_string()],
+        },
+        Change::DelOption {
+            section: "lan".to_string(),
+            option: "proto".to_string(),
+        },
+    ];
+    uci.can_apply(&patch)?;
+    assert_eq!(uci.to_string(), original);
+
+    let missing = vec![Change::SetOption {
+        section: "guest".to_string(),
+        option: "proto".to_string(),
+        values: vec!["dhcp".to_string()],
+    }];
+    assert!(uci.can_apply(&missing).is_err());
+    let conflict = vec![Change::AddSection {
+        typ: "device".to_string(),
+        name: "lan".to_string(),
+    }];
+    assert!(uci.can_apply(&conflict).is_err());
+    assert!(uci.apply(&[patch.clone(), missing].concat()).is_err());
+    assert_eq!(uci.to_string(), original);
+
+    uci.apply(&patch)?;
+    assert_eq!(uci.get_scalar("wan", "proto")?, "dhcp");
+    assert!(uci.get_option("lan", "proto").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_multiple_packages() -> Result<()> {
+    let input = "\npackage 'network'\n\nconfig interface 'lan'\n\toption proto 'static'\n\npackage 'wireless'\n\nconfig wifi-device 'radio0'\n\toption channel '11'\n\npackage 'network'\n\nconfig interface 'wan'\n\toption proto 'dhcp'\n\n";
+    let mut uci: Uci = input.parse()?;
+    assert_eq!(uci.get_package(), "network");
+
+    let packages: Vec<(&str, &str)> = uci
+        .sections_iter()
+        .map(|sec| (sec.name.as_str(), sec.package.as_str()))
+        .collect();
+    assert_eq!(
+        packages,
+        vec![("lan", ""), ("radio0", "wireless"), ("wan", "")]
+    );
+
+    uci.add_section("interface", "guest")?;
+    assert_eq!(
+        uci.to_string(),
+        "\npackage 'network'\n\nconfig interface 'lan'\n\toption proto 'static'\n\nconfig interface 'wan'\n\toption proto 'dhcp'\n\nconfig interface 'guest'\n\npackage 'wireless'\n\nconfig wifi-device 'radio0'\n\toption channel '11'\n\n"
+    );
+    assert_eq!(uci.to_string().parse::<Uci>()?.to_string(), uci.to_string());
+    Ok(())
+}
+
+#[test]
+fn test_uci_comment_accessors() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config_comments");
+    let uci = parse_raw_to_uci("uci_config_comments", uci_str.to_string())?;
+    assert_eq!(
+        uci.header_comments(),
+        ["# generated by the uci_rs test suite".to_string()]
+    );
+    assert_eq!(
+        uci.section_comments("main")?,
+        ["# luci core settings".to_string()]
+    );
+    assert_eq!(
+        uci.section_comments("lan")?,
+        ["# local network".to_string()]
+    );
+    assert!(uci.section_comments("globals")?.is_empty());
+    assert!(uci.section_comments("missing").is_err());
+
+    let wan = uci.get_cached("wan").unwrap();
+    assert!(uci.section_comments("wan")?.is_empty());
+    assert_eq!(wan.inline_comment.as_deref(), Some("# upstream"));
+    assert!(wan.get("ifname").unwrap().comments.is_empty());
+    let lan = uci.get_cached("lan").unwrap();
+    let proto = lan.get("proto").unwrap();
+    assert_eq!(proto.inline_comment_at(0), Some("# no dhcp on lan"));
+    assert!(lan.get("netmask").unwrap().comments.is_empty());
+    let servers = uci.get_cached("ntp").unwrap().get("server").unwrap();
+    assert_eq!(servers.inline_comment_at(0), None);
+    assert_eq!(servers.inline_comment_at(1), Some("# fallback pool"));
+
+    let uci = Uci::new("empty");
+    assert!(uci.header_comments().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_uci_inline_comment_on_unnamed_section() -> Result<()> {
+    let input = "config rule # note\n\toption name 'a'\n";
+    let uci: Uci = input.parse()?;
+    let rule = uci.get_cached("@rule[0]").unwrap();
+    assert_eq!(rule.name, "");
+    assert_eq!(rule.inline_comment.as_deref(), Some("# note"));
+    assert_eq!(*uci.get_option("@rule[0]", "name")?.1, ["a".to_string()]);
+    assert_eq!(uci.to_string(), input);
+
+    let reparsed: Uci = uci.to_string().parse()?;
+    assert_eq!(reparsed.to_string(), input);
+    Ok(())
+}
+
+#[test]
+fn test_uci_section_count() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    assert_eq!(uci.section_count(), uci.get_all_sections().len());
+    assert_eq!(Uci::new("empty").section_count(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_uci_write_canonical() -> Result<()> {
+    let first: Uci = "config rule\n\toption name 'b'\nconfig zone 'wan'\n\toption input 'REJECT'\n\toption name 'wan'\nconfig rule\n\toption name 'a'\n\toption src 'wan'\n"
+        .parse()?;
+    let second: Uci = "config zone 'wan'\n\toption name 'wan'\n\toption input 'REJECT'\nconfig rule\n\toption src 'wan'\n\toption name 'a'\nconfig rule\n\toption name 'b'\n"
+        .parse()?;
+    assert_ne!(first.to_string(), second.to_string());
+
+    let (mut first_out, mut second_out) = (vec![], vec![]);
+    first.write_canonical(&mut first_out)?;
+    second.write_canonical(&mut second_out)?;
+    assert_eq!(first_out, second_out);
+    assert_eq!(
+        String::from_utf8(first_out).unwrap(),
+        "\nconfig rule\n\toption name 'a'\n\toption src 'wan'\n\nconfig rule\n\toption name 'b'\n\nconfig zone 'wan'\n\toption input 'REJECT'\n\toption name 'wan'\n\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_validate_names() -> Result<()> {
+    let mut uci = Uci::new("test");
+    assert!(uci.add_section("t", "bad name").is_err());
+    assert!(uci.add_section("bad type", "name").is_err());
+    assert!(uci.add_section("t", "it's").is_err());
+    uci.add_section("t", "")?;
+    uci.add_section("wifi-iface", "radio_0")?;
+
+    assert!(uci.set_option("radio_0", "bad option", vec!["x"]).is_err());
+    assert!(uci.set_option("@t[0]", "opt.name", vec!["x"]).is_err());
+    assert!(uci.add_list_item("radio_0", "", "x").is_err());
+    uci.set_option("radio_0", "ssid", vec!["with spaces is fine"])?;
+    assert!(uci.rename_section("radio_0", "new name").is_err());
+    assert!(uci.rename_option("radio_0", "ssid", "s s").is_err());
+    assert_eq!(uci.section_count(), 2);
+    assert_eq!(uci.to_string().parse::<Uci>()?.to_string(), uci.to_string());
+    Ok(())
+}
+
+#[test]
+fn test_uci_validate_values_printable() -> Result<()> {
+    let mut uci = Uci::new("test");
+    uci.add_section("rule", "")?;
+    uci.set_option("@rule[0]", "name", vec!["tab\tseparated"])?;
+    uci.validate_values_printable()?;
+
+    uci.add_list_item("@rule[0]", "proto", "tcp")?;
+    uci.add_list_item("@rule[0]", "proto", "u\u{0}dp")?;
+    let err = uci.validate_values_printable().unwrap_err();
+    assert_eq!(
+        err.message,
+        "option of @rule[0].proto contains a control character"
+    );
+
+    uci.set_option("@rule[0]", "proto", vec!["tcp"])?;
+    uci.set_option("@rule[0]", "name", vec!["\u{1b}[31m"])?;
+    assert!(uci.validate_values_printable().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_tokenize() {
+    let input = "package 'net'\n# lan\nconfig interface 'lan'\n\tlist dns '1.1.1.1'\n";
+    let tokens: Vec<Token> = tokenize("net", input).collect();
+
+    let types: Vec<&ScanTokenType> = tokens.iter().map(|tok| &tok.typ).collect();
+    assert_eq!(
+        types,
+        vec![
+            &ScanTokenType::Package,
+            &ScanTokenType::Comment,
+            &ScanTokenType::Section,
+            &ScanTokenType::List,
+        ]
+    );
+
+    let section = &tokens[2].items;
+    assert_eq!(section[0].typ, TokenItemType::Ident);
+    assert_eq!(section[0].val, "interface");
+    assert_eq!(section[1].typ, TokenItemType::String);
+    assert_eq!(section[1].val, "lan");
+    assert_eq!((section[1].line, section[1].pos), (3, 37));
+
+    let list = &tokens[3].items;
+    assert_eq!(list[0].val, "dns");
+    assert_eq!(list[1].val, "1.1.1.1");
+
+    let broken: Vec<Token> = tokenize("broken", "config 'lan\n").collect();
+    assert_eq!(broken.last().unwrap().typ, ScanTokenType::Error);
+}
+
+#[test]
+fn test_uci_diff() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let old = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    assert!(old.diff(&old).is_empty());
+
+    let mut new = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    new.set_option("@system[0]", "timezone", vec!["CET"])?;
+    assert_eq!(
+        old.diff(&new),
+        vec![ConfigChange::OptionChanged {
+            section: "@system[0]".to_string(),
+            option: "timezone".to_string(),
+            from: Some("UTC".to_string()),
+            to: Some("CET".to_string()),
+        }]
+    );
+
+    new.del_option("main", "lang")?;
+    new.add_list_item("themes", "extra", "dark")?;
+    new.del_section("ccache")?;
+    new.add_section("internal", "cache")?;
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            ConfigChange::OptionChanged {
+                section: "main".to_string(),
+                option: "lang".to_string(),
+                from: Some("auto".to_string()),
+                to: None,
+            },
+            ConfigChange::SectionRemoved {
+                section: "ccache".to_string(),
+                typ: "internal".to_string(),
+            },
+            ConfigChange::ListChanged {
+                section: "themes".to_string(),
+                option: "extra".to_string(),
+                from: vec![],
+                to: vec!["dark".to_string()],
+            },
+            ConfigChange::OptionChanged {
+                section: "@system[0]".to_string(),
+                option: "timezone".to_string(),
+                from: Some("UTC".to_string()),
+                to: Some("CET".to_string()),
+            },
+            ConfigChange::SectionAdded {
+                section: "cache".to_string(),
+                typ: "internal".to_string(),
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_get_cached() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let mut uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    let lookup = |uci: &Uci, name: &str| {
+        let cached = uci.get_cached(name).map(|sec| sec.sec_type.clone());
+        assert_eq!(cached, uci.get_section(name).ok().map(|(typ, _)| typ));
+        cached.is_some()
+    };
+
+    for (_, name) in uci.get_all_sections() {
+        assert!(lookup(&uci, &name));
+        assert!(lookup(&uci, &name));
+    }
+    assert!(!lookup(&uci, "missing"));
+
+    uci.move_section("sauth", 0)?;
+    assert!(lookup(&uci, "sauth"));
+    assert!(lookup(&uci, "main"));
+
+    uci.del_section("main")?;
+    assert!(!lookup(&uci, "main"));
+    assert!(lookup(&uci, "themes"));
+
+    uci.rename_section("themes", "styles")?;
+    assert!(!lookup(&uci, "themes"));
+    assert!(lookup(&uci, "styles"));
+
+    uci.add_section("internal", "extra")?;
+    assert!(lookup(&uci, "extra"));
+
+    uci.swap_sections(0, 1)?;
+    assert!(lookup(&uci, "sauth"));
+    uci.copy_section("extra", "copied")?;
+    assert!(lookup(&uci, "copied"));
+    uci.merge_from_str("config internal 'merged'\n")?;
+    assert!(lookup(&uci, "merged"));
+    uci.clear();
+    assert!(!lookup(&uci, "sauth"));
+    Ok(())
+}
+
+#[test]
+fn test_uci_is_sync() {
+    fn assert_sync<T: Sync>() {}
+    assert_sync::<Uci>();
+}
+
+#[test]
+fn test_uci_quoted_section_type_round_trip() -> Result<()> {
+    let uci_str = "config 'interface' 'lan'\n\toption proto 'static'\n\nconfig \"rule\"\n";
+    let uci = parse_raw_to_uci("network", uci_str.to_string())?;
+    let out = uci.to_string();
+    assert_eq!(
+        out,
+        "config interface 'lan'\n\toption proto 'static'\n\nconfig rule\n"
+    );
+    let reparsed = parse_raw_to_uci("network", out.clone())?;
+    assert_eq!(reparsed.to_string(), out);
+    assert_eq!(reparsed.get_section("lan")?.0, "interface");
+
+    assert!(parse_raw_to_uci("network", "config 'my type' 'lan'\n".to_string()).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_uci_quote_style_round_trip() -> Result<()> {
+    let uci_str = "\npackage 'quotes'\n\nconfig interface 'lan'\n\toption proto \"static\"\n\toption ipaddr '10.0.0.1'\n\tlist dns \"1.1.1.1\"\n\tlist dns '9.9.9.9'\n\tlist dns \"8.8.8.8\"\n\toption desc \"it's \\\"lan\\\"\"\n\n";
+    let mut uci = parse_raw_to_uci("quotes", uci_str.to_string())?;
+    assert_eq!(uci.to_string(), uci_str);
+    assert_eq!(
+        uci.get_option("lan", "desc")?.1,
+        &vec![r#"it's "lan""#.to_string()]
+    );
+
+    uci.del_list_item("lan", "dns", "1.1.1.1")?;
+    uci.set_option("lan", "proto", vec!["dhcp"])?;
+    uci.add_list_item("lan", "dns", "4.4.4.4")?;
+    assert_eq!(
+        uci.to_string(),
+        "\npackage 'quotes'\n\nconfig interface 'lan'\n\toption proto 'dhcp'\n\toption ipaddr '10.0.0.1'\n\tlist dns '9.9.9.9'\n\tlist dns \"8.8.8.8\"\n\tlist dns '4.4.4.4'\n\toption desc \"it's \\\"lan\\\"\"\n\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uci_round_trip_without_sections() -> Result<()> {
+    for (input, expected) in [
+        ("", ""),
+        ("\n\n", "\n\n"),
+        ("# notes\n# more\n", "# notes\n# more\n"),
+        ("package 'net'\n", "package 'net'\n"),
+        ("package 'net'\n# notes\n", "package 'net'\n\n# notes\n"),
+    ] {
+        let uci = parse_raw_to_uci("empty", input.to_string())?;
+        let out = uci.to_string();
+        assert_eq!(out, expected);
+        let reparsed = parse_raw_to_uci("empty", out.clone())?;
+        assert_eq!(reparsed.to_string(), out);
+        assert_eq!(reparsed.get_package(), uci.get_package());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_uci_round_trip_crlf() -> Result<()> {
+    let input = "\r\n# lan\r\nconfig interface 'lan'\r\n\toption proto 'static'\r\n\r\n";
+    let uci = parse_raw_to_uci("crlf", input.to_string())?;
+    let out = uci.to_string();
+    assert_eq!(out, input.replace("\r\n", "\n"));
+    assert_eq!(parse_raw_to_uci("crlf", out.clone())?.to_string(), out);
+    Ok(())
+}
+
+#[test]
+fn test_uci_map_values() -> Result<()> {
+    let uci_str = include_str!(".test_data/uci_config");
+    let mut uci = parse_raw_to_uci("uci_config", uci_str.to_string())?;
+    let relocate = |rest: &str| format!("/rom/etc/{}", rest);
+    uci.map_values(|_, _, value| value.strip_prefix("/etc/").map(relocate));
+    assert!(uci.is_modified());
+    assert_eq!(
+        uci.get_option("flash_keep", "uci")?.1,
+        &vec!["/rom/etc/config/".to_string()]
+    );
+    assert!(!uci.to_string().contains("'/etc/"));
+    Ok(())
+}
+
+#[test]
+fn test_uci_error_kinds() -> Result<()> {
+    let mut uci = Uci::new("network");
+    uci.add_section("interface", "lan")?;
+
+    let err = uci.get_section("wan").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let err = uci.set_option("wan", "proto", vec!["dhcp"]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert_eq!(err.to_string(), "section 'wan' not found");
+    assert_eq!(err.to_string(), err.message);
+
+    let err = uci.add_section("inter face", "").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidName);
+    let err = uci.get_section("@interface[x]").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidName);
+
+    uci.add_section("interface", "wan")?;
+    let err = uci.rename_section("wan", "lan").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+
+    let err = "config 'broken\n".parse::<Uci>().err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Parse);
+    assert_eq!(Error::new("other").kind(), ErrorKind::Other);
+    Ok(())
+}
+
+#[test]
+fn test_uci_error_boxed() {
+    fn lookup(uci: &Uci) -> std::result::Result<String, Box<dyn std::error::Error>> {
+        Ok(uci.get_option_last("lan", "proto")?.1.unwrap_or_default())
+    }
+    let uci: Uci = "config interface 'lan'\n\toption proto 'static'\n"
+        .parse()
+        .unwrap();
+    assert_eq!(lookup(&uci).unwrap(), "static");
+
+    let err = lookup(&Uci::new("network")).unwrap_err();
+    assert!(err.source().is_none());
+    assert_eq!(
+        err.to_string(),
+        err.downcast_ref::<Error>().unwrap().message
+    );
+}
+
+#[test]
+fn test_uci_parse_empty_option() -> Result<()> {
+    let input = "\nconfig interface 'lan'\n\toption ipaddr\n\toption proto 'static'\n\n";
+    assert!(parse_raw_to_uci("network", input.to_string()).is_err());
+
+    let opts = ParseOptions {
+        allow_empty_option: true,
+        ..ParseOptions::default()
+    };
+    let uci = parse_raw_to_uci_with("network", input.to_string(), opts)?;
+    assert_eq!(uci.get_option("lan", "ipaddr")?.1, &vec![String::new()]);
+    assert_eq!(
+        uci.to_string(),
+        "\nconfig interface 'lan'\n\toption ipaddr ''\n\toption proto 'static'\n\n"
+    );
+    Ok(())
 }